@@ -0,0 +1,370 @@
+//! Switching activity and power estimation
+//!
+//! Once enabled with `Board::enable_activity`, the board counts the toggles (Low <-> High)
+//! of every pin of its chips and of every trace while it runs.
+//! A `PowerModel` turns these counts into an energy and an average power per chip and per net:
+//! ```
+//! use std::time::Duration;
+//! use virt_ic::{
+//!     activity::{ChipPower, PowerModel},
+//!     chip::{gates::GateNand, ChipRegistry},
+//!     Board,
+//! };
+//!
+//! let mut board = Board::from_netlist(
+//!     "part PWR virt_ic::Generator
+//!      part U1 virt_ic::GateNand
+//!      net VCC PWR.1 U1.14
+//!      net GND PWR.2 U1.7
+//!      net Y U1.3 U1.1 U1.2",
+//!     &ChipRegistry::new(),
+//! )
+//! .unwrap();
+//! board.enable_activity();
+//! board.run_during(Duration::from_millis(10), Duration::from_millis(1));
+//!
+//! let mut model = PowerModel::default();
+//! model.set_chip_power(GateNand::TYPE, ChipPower::new(2e-12, 1e-6));
+//! let report = board.activity_report(&model).unwrap();
+//! println!("{report}");
+//! ```
+use super::{naming, Chip, Socket, State, Trace};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Energy coefficients of a chip type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChipPower {
+    /// Energy spent by a toggle of one of its pins, in joules
+    pub energy_per_toggle: f64,
+    /// Power spent whatever its activity, in watts
+    pub static_power: f64,
+}
+
+impl ChipPower {
+    pub fn new(energy_per_toggle: f64, static_power: f64) -> Self {
+        Self {
+            energy_per_toggle,
+            static_power,
+        }
+    }
+}
+
+/// Energy coefficients of the chips and nets of a board
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerModel {
+    chips: HashMap<String, ChipPower>,
+    /// Coefficients of the chip types without their own
+    pub default_chip: ChipPower,
+    /// Energy spent by a toggle of a net, in joules
+    pub net_energy_per_toggle: f64,
+}
+
+impl Default for PowerModel {
+    /// A model where every pin toggle costs 1 pJ and nets cost nothing
+    fn default() -> Self {
+        Self {
+            chips: HashMap::new(),
+            default_chip: ChipPower::new(1e-12, 0.0),
+            net_energy_per_toggle: 0.0,
+        }
+    }
+}
+
+impl PowerModel {
+    /// Set the coefficients of a chip type
+    pub fn set_chip_power(&mut self, chip_type: &str, power: ChipPower) {
+        self.chips.insert(String::from(chip_type), power);
+    }
+
+    pub fn get_chip_power(&self, chip_type: &str) -> ChipPower {
+        self.chips
+            .get(chip_type)
+            .copied()
+            .unwrap_or(self.default_chip)
+    }
+}
+
+/// Toggle counter of a signal
+#[derive(Debug, Clone, Default)]
+struct Counter {
+    /// Last defined state
+    state: Option<bool>,
+    toggles: u64,
+}
+
+impl Counter {
+    fn observe(&mut self, state: &State) {
+        let state = match state {
+            State::High => true,
+            State::Low => false,
+            State::Undefined => return,
+        };
+        if self.state.is_some_and(|last| last != state) {
+            self.toggles += 1;
+        }
+        self.state = Some(state);
+    }
+}
+
+/// The toggle counters of a board
+#[derive(Debug, Default)]
+pub(crate) struct ActivityCounter {
+    duration: Duration,
+    pins: Vec<Vec<Counter>>,
+    nets: Vec<Counter>,
+}
+
+impl ActivityCounter {
+    /// Count the toggles of a step, once the chips ran
+    pub(crate) fn observe(
+        &mut self,
+        elapsed: Duration,
+        traces: &[Rc<RefCell<Trace>>],
+        sockets: &[Rc<RefCell<Socket>>],
+    ) {
+        self.duration += elapsed;
+        self.pins.resize_with(sockets.len(), Vec::new);
+        for (socket, counters) in sockets.iter().zip(&mut self.pins) {
+            let mut socket = socket.borrow_mut();
+            counters.resize_with(socket.get_pin_qty() as usize, Counter::default);
+            for (number, counter) in (1..=socket.get_pin_qty()).zip(counters.iter_mut()) {
+                counter.observe(&socket.get_pin_state(number));
+            }
+        }
+        self.nets.resize_with(traces.len(), Counter::default);
+        for (trace, counter) in traces.iter().zip(&mut self.nets) {
+            counter.observe(&trace.borrow().get_state());
+        }
+    }
+
+    pub(crate) fn report(
+        &self,
+        model: &PowerModel,
+        traces: &[Rc<RefCell<Trace>>],
+        sockets: &[Rc<RefCell<Socket>>],
+    ) -> ActivityReport {
+        let seconds = self.duration.as_secs_f64();
+        let power = |energy: f64| if seconds > 0.0 { energy / seconds } else { 0.0 };
+        let mut chips = vec![];
+        for (socket, counters) in sockets.iter().zip(&self.pins) {
+            let socket = socket.borrow();
+            if !socket.has_chip() {
+                continue;
+            }
+            let coefficients = model.get_chip_power(socket.get_type());
+            let pin_toggles: Vec<u64> = counters.iter().map(|counter| counter.toggles).collect();
+            let toggles = pin_toggles.iter().sum();
+            let energy = toggles as f64 * coefficients.energy_per_toggle
+                + coefficients.static_power * seconds;
+            chips.push(ChipActivity {
+                socket: naming::socket_label(&socket),
+                chip_type: String::from(socket.get_type()),
+                toggles,
+                pin_toggles,
+                energy,
+                power: power(energy),
+            });
+        }
+        let nets: Vec<NetActivity> = traces
+            .iter()
+            .zip(&self.nets)
+            .enumerate()
+            .map(|(index, (trace, counter))| {
+                let energy = counter.toggles as f64 * model.net_energy_per_toggle;
+                NetActivity {
+                    trace: naming::trace_label(index, trace),
+                    toggles: counter.toggles,
+                    energy,
+                    power: power(energy),
+                }
+            })
+            .collect();
+        let energy = chips
+            .iter()
+            .map(|chip| chip.energy)
+            .chain(nets.iter().map(|net| net.energy))
+            .fold(0.0, |total, energy| total + energy);
+        ActivityReport {
+            duration: self.duration,
+            chips,
+            nets,
+            energy,
+            power: power(energy),
+        }
+    }
+}
+
+/// Activity and energy of a chip
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChipActivity {
+    /// Designator of the socket, or the chip type if it has none
+    pub socket: String,
+    pub chip_type: String,
+    /// Toggles of all its pins
+    pub toggles: u64,
+    /// Toggles of each pin, the first one being pin 1
+    pub pin_toggles: Vec<u64>,
+    /// Energy in joules
+    pub energy: f64,
+    /// Average power in watts
+    pub power: f64,
+}
+
+/// Activity and energy of a net
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetActivity {
+    /// Name of the trace, or its number if it has none
+    pub trace: String,
+    pub toggles: u64,
+    /// Energy in joules
+    pub energy: f64,
+    /// Average power in watts
+    pub power: f64,
+}
+
+/// Activity and energy of a board since the counters were enabled or reset
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityReport {
+    /// Simulated time the activity was counted for
+    pub duration: Duration,
+    pub chips: Vec<ChipActivity>,
+    pub nets: Vec<NetActivity>,
+    /// Total energy in joules
+    pub energy: f64,
+    /// Average total power in watts
+    pub power: f64,
+}
+
+impl fmt::Display for ActivityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "activity over {:?}", self.duration)?;
+        writeln!(f, "chips:")?;
+        for chip in &self.chips {
+            writeln!(
+                f,
+                "  {:<12} {:<24} {:>8} toggles {:>12.3e} J {:>12.3e} W",
+                chip.socket, chip.chip_type, chip.toggles, chip.energy, chip.power
+            )?;
+        }
+        writeln!(f, "nets:")?;
+        for net in &self.nets {
+            writeln!(
+                f,
+                "  {:<12} {:>8} toggles {:>12.3e} J {:>12.3e} W",
+                net.trace, net.toggles, net.energy, net.power
+            )?;
+        }
+        write!(f, "total: {:.3e} J, {:.3e} W", self.energy, self.power)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chip::ChipInfo, Board, Pin, PinType};
+
+    /// A clock toggling its output at every step, and reading it back on its second pin
+    struct Toggler {
+        pins: [Rc<RefCell<Pin>>; 2],
+    }
+
+    impl Toggler {
+        const TYPE: &'static str = "test::Toggler";
+
+        fn new() -> Self {
+            Self {
+                pins: [
+                    Rc::new(RefCell::new(Pin::new(1, 1, PinType::Output))),
+                    Rc::new(RefCell::new(Pin::new(1, 2, PinType::Input))),
+                ],
+            }
+        }
+    }
+
+    impl Chip for Toggler {
+        fn get_uuid(&self) -> u128 {
+            1
+        }
+        fn get_type(&self) -> &str {
+            Self::TYPE
+        }
+        fn run(&mut self, _: Duration) {
+            let mut output = self.pins[0].borrow_mut();
+            output.state = State::from(output.state != State::High);
+        }
+        fn get_pin_qty(&self) -> u8 {
+            2
+        }
+        fn _get_pin(&mut self, pin: u8) -> Rc<RefCell<Pin>> {
+            self.pins[pin as usize - 1].clone()
+        }
+        fn get_info(&self) -> ChipInfo {
+            ChipInfo {
+                name: "Toggler",
+                description: "",
+                data: String::new(),
+            }
+        }
+    }
+
+    fn assert_near(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 1e-12,
+            "{} instead of {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn toggles_are_counted_and_priced() {
+        let mut board = Board::new();
+        let socket = board.new_socket_with(Box::new(Toggler::new()));
+        socket.borrow_mut().set_name("CLK");
+        let trace = board.new_trace();
+        trace.borrow_mut().set_name("OUT");
+        for pin in 1..=2 {
+            trace
+                .borrow_mut()
+                .connect(socket.borrow_mut().get_pin(pin).unwrap());
+        }
+        board.new_trace().borrow_mut().set_name("IDLE");
+        assert!(board.activity_report(&PowerModel::default()).is_none());
+        board.enable_activity();
+        // 500 Hz: High after the first step, then 9 toggles in 10 steps
+        board.run_during(Duration::from_millis(10), Duration::from_millis(1));
+
+        let mut model = PowerModel::default();
+        model.set_chip_power(Toggler::TYPE, ChipPower::new(2e-12, 1e-6));
+        model.net_energy_per_toggle = 5e-12;
+        let report = board.activity_report(&model).unwrap();
+        assert_eq!(report.duration, Duration::from_millis(10));
+
+        let chip = &report.chips[0];
+        assert_eq!(chip.socket, "CLK");
+        // the input reads the trace one step late, it's undefined during the first step
+        assert_eq!(chip.pin_toggles, [9, 8]);
+        assert_eq!(chip.toggles, 17);
+        let chip_energy = 17.0 * 2e-12 + 1e-6 * 0.01;
+        assert_near(chip.energy, chip_energy);
+        assert_near(chip.power, chip_energy / 0.01);
+
+        assert_eq!(report.nets[0].trace, "OUT");
+        assert_eq!(report.nets[0].toggles, 9);
+        assert_near(report.nets[0].energy, 9.0 * 5e-12);
+        assert_eq!(report.nets[1].toggles, 0);
+        assert_eq!(report.nets[1].energy, 0.0);
+        assert_near(report.energy, chip_energy + 9.0 * 5e-12);
+        assert_near(report.power, report.energy / 0.01);
+
+        board.reset_activity();
+        let report = board.activity_report(&model).unwrap();
+        assert_eq!(report.duration, Duration::ZERO);
+        assert_eq!(report.energy, 0.0);
+        assert_eq!(report.power, 0.0);
+    }
+}
@@ -1,8 +1,20 @@
 use super::{
-    save::{SavedBoard, SavedSocket},
-    Chip, Socket, Trace,
+    activity::{ActivityCounter, ActivityReport, PowerModel},
+    chip::ChipRegistry,
+    compiled::CompiledNetwork,
+    dot,
+    fault::{Fault, InjectedFault},
+    glitch::{Glitch, GlitchDetector},
+    history::{Checkpoint, History},
+    netlist,
+    profile::{ProfileReport, Profiler},
+    save::{SaveFormat, SavedBoard, SavedSocket},
+    snapshot::{BoardSnapshot, SocketSnapshot},
+    truth_table::{self, TruthTable},
+    verilog, Chip, Error, LogicModel, Result, Socket, State, Trace,
 };
 use std::cell::RefCell;
+use std::io::{Read, Write};
 use std::rc::Rc;
 use std::time::{Duration, Instant};
 
@@ -11,6 +23,13 @@ use std::time::{Duration, Instant};
 pub struct Board {
     traces: Vec<Rc<RefCell<Trace>>>,
     sockets: Vec<Rc<RefCell<Socket>>>,
+    elapsed: Duration,
+    history: Option<History>,
+    faults: Vec<InjectedFault>,
+    glitches: Option<GlitchDetector>,
+    activity: Option<ActivityCounter>,
+    profiler: Option<Profiler>,
+    logic_model: LogicModel,
 }
 
 impl Board {
@@ -19,12 +38,20 @@ impl Board {
         Self {
             traces: vec![],
             sockets: vec![],
+            elapsed: Duration::new(0, 0),
+            history: None,
+            faults: vec![],
+            glitches: None,
+            activity: None,
+            profiler: None,
+            logic_model: LogicModel::Binary,
         }
     }
 
     /// Create a new trace and return it
     pub fn new_trace(&mut self) -> Rc<RefCell<Trace>> {
         let trace = Rc::new(RefCell::new(Trace::new()));
+        trace.borrow_mut().set_logic_model(self.logic_model);
         self.traces.push(trace);
         // unwrap because we just pushed a value so there's no reason to get a None here
         self.traces.last_mut().unwrap().clone()
@@ -43,6 +70,7 @@ impl Board {
     pub fn new_socket_with(&mut self, chip: Box<dyn Chip>) -> Rc<RefCell<Socket>> {
         let socket = Rc::new(RefCell::new(Socket::new()));
         socket.borrow_mut().plug(chip);
+        socket.borrow_mut().set_logic_model(self.logic_model);
         self.sockets.push(socket);
         // unwrap because we just pushed a value so there's no reason to get a None here
         self.sockets.last_mut().unwrap().clone()
@@ -65,19 +93,279 @@ impl Board {
         None
     }
 
+    /// Get a socket by its designator
+    pub fn get_socket_by_name(&self, name: &str) -> Option<Rc<RefCell<Socket>>> {
+        self.sockets
+            .iter()
+            .find(|socket| socket.borrow().get_name() == Some(name))
+            .cloned()
+    }
+
+    /// Get a trace by its name
+    pub fn get_trace_by_name(&self, name: &str) -> Option<Rc<RefCell<Trace>>> {
+        self.traces
+            .iter()
+            .find(|trace| trace.borrow().get_name() == Some(name))
+            .cloned()
+    }
+
     /// Run the circuit for a certain amount of time
     /// You must use `use_during` since it provides more accurate simulation by stepping
     pub fn run(&mut self, time_elapsed: Duration) {
         // TODO: find a way to update the traces accurately
         // current issue : the order of the traces affects the order of the links
-        for trc in &mut self.traces {
-            trc.borrow_mut().communicate();
+        for fault in &mut self.faults {
+            fault.update(self.elapsed);
+        }
+        match self.profiler.as_mut() {
+            Some(profiler) => profiler.communicate(&self.traces),
+            None => {
+                for trc in &mut self.traces {
+                    trc.borrow_mut().communicate();
+                }
+            }
+        }
+        if let Some(detector) = self.glitches.as_mut() {
+            detector.observe(self.elapsed, &self.traces, &self.sockets);
+        }
+        for fault in &self.faults {
+            fault.stick(self.elapsed);
+        }
+        match self.profiler.as_mut() {
+            Some(profiler) => profiler.run(&self.sockets, time_elapsed),
+            None => {
+                for skt in &mut self.sockets {
+                    skt.borrow_mut().run(time_elapsed);
+                }
+            }
+        }
+        for fault in &self.faults {
+            fault.stick(self.elapsed);
+        }
+        if let Some(activity) = self.activity.as_mut() {
+            activity.observe(time_elapsed, &self.traces, &self.sockets);
+        }
+        self.elapsed += time_elapsed;
+        if let Some(history) = self.history.as_mut() {
+            history.record(time_elapsed);
+        }
+        if self
+            .history
+            .as_ref()
+            .is_some_and(|history| history.needs_checkpoint(self.elapsed))
+        {
+            let checkpoint = self.checkpoint();
+            if let Some(history) = self.history.as_mut() {
+                history.push(checkpoint);
+            }
+        }
+    }
+
+    /// Get the simulated time since the board was created
+    pub fn get_elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub(crate) fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+    }
+
+    /// Record the glitches on the traces, pulses shorter than `min_pulse` and multiple transitions while settling
+    pub fn enable_glitch_detection(&mut self, min_pulse: Duration) {
+        self.glitches = Some(GlitchDetector::new(min_pulse));
+    }
+
+    pub fn disable_glitch_detection(&mut self) {
+        self.glitches = None;
+    }
+
+    /// Get the glitches recorded since the detection was enabled or cleared
+    pub fn get_glitches(&self) -> &[Glitch] {
+        self.glitches
+            .as_ref()
+            .map_or(&[], |detector| detector.glitches.as_slice())
+    }
+
+    pub fn clear_glitches(&mut self) {
+        if let Some(detector) = self.glitches.as_mut() {
+            detector.glitches.clear();
+        }
+    }
+
+    /// Count the toggles of the pins and traces while the board runs, see `activity_report`
+    pub fn enable_activity(&mut self) {
+        self.activity = Some(ActivityCounter::default());
+    }
+
+    pub fn disable_activity(&mut self) {
+        self.activity = None;
+    }
+
+    /// Reset the toggle counters to zero
+    pub fn reset_activity(&mut self) {
+        if self.activity.is_some() {
+            self.enable_activity();
+        }
+    }
+
+    /// Get the activity per chip and per net and estimate their energy with a power model
+    /// Returns `None` if the activity isn't counted.
+    pub fn activity_report(&self, model: &PowerModel) -> Option<ActivityReport> {
+        self.activity
+            .as_ref()
+            .map(|activity| activity.report(model, &self.traces, &self.sockets))
+    }
+
+    /// Set the value set of the traces and the built-in gates, see `LogicModel`
+    /// A chip plugged afterward in an empty socket keeps the binary model until it's set again.
+    pub fn set_logic_model(&mut self, model: LogicModel) {
+        self.logic_model = model;
+        for trace in &self.traces {
+            trace.borrow_mut().set_logic_model(model);
         }
-        for skt in &mut self.sockets {
-            skt.borrow_mut().run(time_elapsed);
+        for socket in &self.sockets {
+            socket.borrow_mut().set_logic_model(model);
         }
     }
 
+    pub fn get_logic_model(&self) -> LogicModel {
+        self.logic_model
+    }
+
+    /// Measure the time spent in each socket and trace while the board runs, see `profile_report`
+    pub fn enable_profiling(&mut self) {
+        self.profiler = Some(Profiler::default());
+    }
+
+    pub fn disable_profiling(&mut self) {
+        self.profiler = None;
+    }
+
+    /// Reset the measures to zero
+    pub fn reset_profiling(&mut self) {
+        if self.profiler.is_some() {
+            self.enable_profiling();
+        }
+    }
+
+    /// Get the time spent in each socket and trace, sorted from the slowest
+    /// Returns `None` if the profiling isn't enabled.
+    pub fn profile_report(&self) -> Option<ProfileReport> {
+        self.profiler
+            .as_ref()
+            .map(|profiler| profiler.report(&self.traces, &self.sockets))
+    }
+
+    /// Inject a fault applied while the board runs, its times are compared to `get_elapsed`
+    pub fn inject_fault(&mut self, fault: Fault) -> Result<()> {
+        let fault = InjectedFault::new(self, fault)?;
+        self.faults.push(fault);
+        Ok(())
+    }
+
+    /// Remove the injected faults, the traces they forced are released
+    /// Flipped memory bits stay flipped.
+    pub fn clear_faults(&mut self) {
+        for fault in self.faults.drain(..) {
+            fault.clear();
+        }
+    }
+
+    pub(crate) fn get_injected_faults(&self) -> Vec<InjectedFault> {
+        self.faults.clone()
+    }
+
+    /// Put back faults taken with `get_injected_faults`, along with whether they were applied
+    pub(crate) fn set_injected_faults(&mut self, faults: Vec<InjectedFault>) {
+        self.faults = faults;
+    }
+
+    /// Take a checkpoint of the board every `interval` of simulated time, keeping the last `capacity` ones
+    /// This allows to step the simulation backward, see `step_back` and `History`
+    pub fn enable_history(&mut self, interval: Duration, capacity: usize) {
+        let mut history = History::new(interval, capacity);
+        history.push(self.checkpoint());
+        self.history = Some(history);
+    }
+
+    /// Stop taking checkpoints and drop the existing ones
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    pub fn get_history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            time: self.elapsed,
+            snapshot: self.snapshot(),
+            forced: self
+                .traces
+                .iter()
+                .map(|trace| trace.borrow().get_forced())
+                .collect(),
+            faults: self.faults.clone(),
+            steps: vec![],
+        }
+    }
+
+    /// Step the simulation backward by `duration`, see `rewind_to`
+    pub fn step_back(&mut self, duration: Duration) -> Result<Duration> {
+        self.rewind_to(self.elapsed.checked_sub(duration).unwrap_or_default())
+    }
+
+    /// Rewind the simulation to the simulated `time`
+    /// The nearest checkpoint before `time` is restored, then the steps that followed it are run again
+    /// up to `time`. Returns the time actually reached, that can be earlier than `time` if it falls within a step.
+    /// Checkpoints after the reached time are dropped.
+    /// The traces are forced and the faults are injected like at the time of the checkpoint, and the replay applies them.
+    /// The replay leaves the glitches, the activity and the profile as they were.
+    pub fn rewind_to(&mut self, time: Duration) -> Result<Duration> {
+        let mut history = self.history.take().ok_or(Error::NoCheckpoint(time))?;
+        let index = match history.checkpoints.iter().rposition(|cp| cp.time <= time) {
+            Some(index) => index,
+            None => {
+                self.history = Some(history);
+                return Err(Error::NoCheckpoint(time));
+            }
+        };
+        history.checkpoints.truncate(index + 1);
+        let checkpoint = &mut history.checkpoints[index];
+        if let Err(err) = self.restore(&checkpoint.snapshot) {
+            self.history = Some(history);
+            return Err(err);
+        }
+        self.elapsed = checkpoint.time;
+        for (trace, forced) in self.traces.iter().zip(&checkpoint.forced) {
+            match forced {
+                Some(state) => trace.borrow_mut().force(state.clone()),
+                None => trace.borrow_mut().release(),
+            }
+        }
+        self.faults = checkpoint.faults.clone();
+        // replay without history and observers, then record the replayed steps again
+        let glitches = self.glitches.take();
+        let activity = self.activity.take();
+        let profiler = self.profiler.take();
+        let steps = std::mem::take(&mut checkpoint.steps);
+        'replay: for (step, count) in steps {
+            for _ in 0..count {
+                if self.elapsed + step > time {
+                    break 'replay;
+                }
+                self.run(step);
+                checkpoint.record(step);
+            }
+        }
+        self.glitches = glitches;
+        self.activity = activity;
+        self.profiler = profiler;
+        self.history = Some(history);
+        Ok(self.elapsed)
+    }
+
     /// Run the circuit for a certain amount of time segmented by a step
     /// The smaller the step the more accurate the simulation will be.
     pub fn run_during(&mut self, duration: Duration, step: Duration) {
@@ -88,6 +376,39 @@ impl Board {
         }
     }
 
+    /// Run the circuit by steps until the states of its traces stop changing
+    /// Returns the number of steps it took, or `None` if the circuit didn't settle within `max_steps`.
+    /// With glitch detection, the traces that change more than once meanwhile are reported.
+    pub fn settle(&mut self, step: Duration, max_steps: usize) -> Option<usize> {
+        if let Some(detector) = self.glitches.as_mut() {
+            detector.begin_settle();
+        }
+        let mut states = self.trace_states();
+        let mut settled = None;
+        for steps in 1..=max_steps {
+            self.run(step);
+            let new_states = self.trace_states();
+            if new_states == states {
+                settled = Some(steps);
+                break;
+            }
+            states = new_states;
+        }
+        if let Some(detector) = self.glitches.as_mut() {
+            // observe the states driven by the last step
+            detector.observe(self.elapsed, &self.traces, &self.sockets);
+            detector.end_settle(self.elapsed, &self.traces, &self.sockets);
+        }
+        settled
+    }
+
+    fn trace_states(&self) -> Vec<State> {
+        self.traces
+            .iter()
+            .map(|trace| trace.borrow().get_state())
+            .collect()
+    }
+
     pub fn run_realtime(&mut self, duration: Duration) {
         let instant = Instant::now();
         let mut old = Instant::now();
@@ -99,12 +420,109 @@ impl Board {
         }
     }
 
-    /// Save the board to a file in RON format
-    pub fn save(&self, filepath: &str) -> std::io::Result<()> {
+    /// Capture the state of every pin and chip of the board in memory
+    ///
+    /// Unlike `save`, restoring a snapshot doesn't rebuild the board, so it's meant for frequent checkpoints.
+    pub fn snapshot(&self) -> BoardSnapshot {
+        let mut sockets = Vec::with_capacity(self.sockets.len());
+        for socket in &self.sockets {
+            let mut socket = socket.borrow_mut();
+            if !socket.has_chip() {
+                sockets.push(None);
+                continue;
+            }
+            let mut pins = Vec::with_capacity(socket.get_pin_qty() as usize);
+            for number in 1..=socket.get_pin_qty() {
+                if let Ok(pin) = socket.get_pin(number) {
+                    pins.push(pin.borrow().clone());
+                }
+            }
+            sockets.push(Some(SocketSnapshot {
+                uuid: socket.get_uuid(),
+                pins,
+                chip: socket.snapshot(),
+            }));
+        }
+        BoardSnapshot { sockets }
+    }
+
+    /// Put back the state captured by `snapshot`
+    ///
+    /// The board must hold the same chips in the same sockets as when the snapshot was taken,
+    /// otherwise nothing is restored and an `Error::InvalidSnapshot` is returned.
+    /// If a chip can't restore its state, the chips restored before it are put back as they were
+    /// and the board is left unchanged.
+    pub fn restore(&mut self, snapshot: &BoardSnapshot) -> Result<()> {
+        if snapshot.sockets.len() != self.sockets.len() {
+            return Err(Error::InvalidSnapshot(format!(
+                "the snapshot has {} sockets, the board has {}",
+                snapshot.sockets.len(),
+                self.sockets.len()
+            )));
+        }
+        for (index, (socket, saved)) in self.sockets.iter().zip(&snapshot.sockets).enumerate() {
+            let socket = socket.borrow();
+            let matches = match saved {
+                Some(saved) => {
+                    socket.get_uuid() == saved.uuid
+                        && socket.get_pin_qty() as usize == saved.pins.len()
+                }
+                None => !socket.has_chip(),
+            };
+            if !matches {
+                return Err(Error::InvalidSnapshot(format!(
+                    "socket {} doesn't hold the same chip as in the snapshot",
+                    index + 1
+                )));
+            }
+        }
+        // keep the current state of the chips, to put it back if one of them fails
+        let current: Vec<Box<dyn std::any::Any>> = self
+            .sockets
+            .iter()
+            .map(|socket| socket.borrow().snapshot())
+            .collect();
+        for (index, (socket, saved)) in self.sockets.iter().zip(&snapshot.sockets).enumerate() {
+            if let Some(saved) = saved {
+                if let Err(err) = socket.borrow_mut().restore(saved.chip.as_ref()) {
+                    for (socket, (current, saved)) in self
+                        .sockets
+                        .iter()
+                        .zip(current.iter().zip(&snapshot.sockets))
+                        .take(index)
+                    {
+                        if saved.is_some() {
+                            // the state was just captured from the same chip, it only fails if it couldn't be captured
+                            let _ = socket.borrow_mut().restore(current.as_ref());
+                        }
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        for (socket, saved) in self.sockets.iter().zip(&snapshot.sockets) {
+            if let Some(saved) = saved {
+                let mut socket = socket.borrow_mut();
+                for (number, saved_pin) in (1..=socket.get_pin_qty()).zip(&saved.pins) {
+                    if let Ok(pin) = socket.get_pin(number) {
+                        let mut pin = pin.borrow_mut();
+                        pin.pin_type = saved_pin.pin_type.clone();
+                        pin.state = saved_pin.state.clone();
+                        pin.level = saved_pin.level;
+                        pin.voltage = saved_pin.voltage;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn to_saved(&self) -> Result<SavedBoard> {
         let mut s_board = SavedBoard::new();
         for socket in &self.sockets {
-            let saved_chip = socket.borrow().save();
+            let saved_chip = socket.borrow().save()?;
             let mut saved_socket = SavedSocket::new();
+            saved_socket.name = socket.borrow().get_name().map(String::from);
             if saved_chip.chip_type != "NULL" {
                 saved_socket.set_chip(saved_chip);
             }
@@ -113,40 +531,263 @@ impl Board {
         for trace in &self.traces {
             s_board.add_trace(trace.borrow().save());
         }
+        Ok(s_board)
+    }
 
+    /// Save the board to a file in RON format
+    pub fn save(&self, filepath: &str) -> Result<()> {
+        self.save_as(filepath, SaveFormat::Ron)
+    }
+
+    /// Save the board to a file in the given format
+    pub fn save_as(&self, filepath: &str, format: SaveFormat) -> Result<()> {
         let file = std::fs::File::create(std::path::Path::new(filepath))?;
-        if let Err(e) = ron::ser::to_writer(file, &s_board) {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::Interrupted,
-                format!("{e:?}"),
-            ))
-        } else {
-            Ok(())
+        self.save_to_writer(std::io::BufWriter::new(file), format)
+    }
+
+    /// Save the board to any writer in the given format
+    pub fn save_to_writer<W: Write>(&self, writer: W, format: SaveFormat) -> Result<()> {
+        self.to_saved()?.write(writer, format)
+    }
+
+    /// Save the board to bytes in the given format
+    pub fn save_to_bytes(&self, format: SaveFormat) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        self.save_to_writer(&mut bytes, format)?;
+        Ok(bytes)
+    }
+
+    /// Save the board to a string in the given text format
+    pub fn save_to_string(&self, format: SaveFormat) -> Result<String> {
+        if !format.is_text() {
+            return Err(Error::NotTextFormat(format));
         }
+        let bytes = self.save_to_bytes(format)?;
+        String::from_utf8(bytes).map_err(|_| Error::NotTextFormat(format))
     }
 
     /// Load a file and create a board according to this file
-    /// You'll need to provide a "chip factory" function as second parameter
-    /// By default it's `virt_ic::chip::virt_ic_chip_factory`
-    /// ```
-    /// use virt_ic::chip::virt_ic_chip_factory;
+    /// Files saved by older versions of the crate are migrated to the current format,
+    /// a missing file, an unknown chip type or corrupted chip data result in an `Error`
+    /// You'll need to provide a `ChipRegistry` that knows every chip type of the board as second parameter
+    /// `ChipRegistry::new()` knows the built-in chips
+    /// ```no_run
+    /// use virt_ic::{chip::ChipRegistry, Board};
     ///
-    /// let mut board = Board::load("my_saved_board.ron", &virt_ic_chip_factory).unwrap();
+    /// let mut board = Board::load("my_saved_board.ron", &ChipRegistry::new()).unwrap();
     /// ```
-    pub fn load(
-        filepath: &str,
-        chip_factory: &dyn Fn(&str) -> Option<Box<dyn Chip>>,
-    ) -> std::io::Result<Self> {
+    pub fn load(filepath: &str, registry: &ChipRegistry) -> Result<Self> {
+        Self::load_as(filepath, SaveFormat::Ron, registry)
+    }
+
+    /// Load a file saved in the given format, see `load`
+    pub fn load_as(filepath: &str, format: SaveFormat, registry: &ChipRegistry) -> Result<Self> {
         let file = std::fs::File::open(std::path::Path::new(filepath))?;
-        let s_board: Result<SavedBoard, ron::Error> = ron::de::from_reader(file);
-        s_board.map_or_else(
-            |err| {
-                Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    format!("{err:?}"),
-                ))
-            },
-            |board| Ok(board.build_board(chip_factory)),
+        Self::load_from_reader(std::io::BufReader::new(file), format, registry)
+    }
+
+    /// Load a board from any reader in the given format, see `load`
+    pub fn load_from_reader<R: Read>(
+        reader: R,
+        format: SaveFormat,
+        registry: &ChipRegistry,
+    ) -> Result<Self> {
+        let mut s_board = SavedBoard::read(reader, format)?;
+        s_board.migrate()?;
+        s_board.build_board(registry)
+    }
+
+    /// Load a board from bytes in the given format, see `load`
+    pub fn load_from_bytes(
+        bytes: &[u8],
+        format: SaveFormat,
+        registry: &ChipRegistry,
+    ) -> Result<Self> {
+        Self::load_from_reader(bytes, format, registry)
+    }
+
+    /// Load a board from a string in the given text format, see `load`
+    pub fn load_from_str(input: &str, format: SaveFormat, registry: &ChipRegistry) -> Result<Self> {
+        if !format.is_text() {
+            return Err(Error::NotTextFormat(format));
+        }
+        Self::load_from_bytes(input.as_bytes(), format, registry)
+    }
+
+    /// Build a board from a text netlist, see the `netlist` module for the format
+    /// You'll need to provide a `ChipRegistry` as second parameter, `ChipRegistry::new()` knows the built-in chips
+    pub fn from_netlist(netlist: &str, registry: &ChipRegistry) -> Result<Self> {
+        Ok(netlist::parse(netlist, registry)?)
+    }
+
+    /// Write the board as a text netlist
+    pub fn to_netlist(&self) -> String {
+        netlist::write(self)
+    }
+
+    /// Export the sockets and traces of the board as a Graphviz DOT graph
+    /// If `with_states` is true, pins and traces are labelled with their current state
+    pub fn to_dot(&self, with_states: bool) -> String {
+        dot::write(self, with_states)
+    }
+
+    /// Export the board as a structural Verilog module, see the `verilog` module for details
+    pub fn to_verilog(&self, module_name: &str) -> String {
+        verilog::write(self, module_name)
+    }
+
+    /// Build a board from a structural Verilog module, see the `verilog` module for the supported subset
+    /// Gates are packed into the built-in gate chips, other instances are created with the `ChipRegistry`
+    pub fn from_verilog(source: &str, registry: &ChipRegistry) -> Result<Self> {
+        Ok(verilog::parse(source, registry)?)
+    }
+
+    /// Compile the built-in gate chips of the board into a levelized network, see `CompiledNetwork`
+    ///
+    /// Fails if the gates form a combinational loop, drive a trace also driven by another chip,
+    /// or if the board uses the `StdLogic` model.
+    pub fn compile(&self) -> Result<CompiledNetwork> {
+        CompiledNetwork::compile(self)
+    }
+
+    /// Drive every combination of the `inputs` traces and observe the `outputs` traces once settled
+    ///
+    /// Traces are given by name, the inputs are forced during the extraction, then released or forced back
+    /// to the state they were forced to before.
+    /// The board keeps the state of the last combination.
+    pub fn truth_table(&mut self, inputs: &[&str], outputs: &[&str]) -> Result<TruthTable> {
+        truth_table::extract(self, inputs, outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testbench::Signal;
+
+    #[test]
+    fn rewinding_keeps_the_analysis_results() {
+        let mut board = Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part CLK virt_ic::Clock1kHz
+             net VCC PWR.1 CLK.4
+             net GND PWR.2 CLK.2
+             net OUT CLK.1",
+            &ChipRegistry::new(),
+        )
+        .unwrap();
+        board.enable_history(Duration::from_millis(5), 4);
+        board.enable_glitch_detection(Duration::from_millis(10));
+        board.enable_activity();
+        board.enable_profiling();
+        board.run_during(Duration::from_millis(12), Duration::from_micros(100));
+
+        let glitches = board.get_glitches().to_vec();
+        let activity = board.activity_report(&PowerModel::default());
+        let calls: u64 = board
+            .profile_report()
+            .unwrap()
+            .entries
+            .iter()
+            .map(|entry| entry.calls)
+            .sum();
+        assert!(!glitches.is_empty());
+
+        board.step_back(Duration::from_millis(3)).unwrap();
+        assert_eq!(board.get_elapsed(), Duration::from_millis(9));
+        assert_eq!(board.get_glitches(), glitches.as_slice());
+        assert_eq!(board.activity_report(&PowerModel::default()), activity);
+        let replayed: u64 = board
+            .profile_report()
+            .unwrap()
+            .entries
+            .iter()
+            .map(|entry| entry.calls)
+            .sum();
+        assert_eq!(replayed, calls);
+    }
+
+    #[test]
+    fn rewinding_applies_the_faults_again() {
+        let mut board = Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part CLK virt_ic::Clock1kHz
+             part U1 virt_ic::GateNot
+             net VCC PWR.1 CLK.4 U1.14
+             net GND PWR.2 CLK.2 U1.7
+             net CLK CLK.1 U1.1 U1.3
+             net Y U1.2
+             net Z U1.4",
+            &ChipRegistry::new(),
+        )
+        .unwrap();
+        let ms = Duration::from_millis;
+        board.enable_history(ms(5), 4);
+        board
+            .inject_fault(Fault::stuck_at(
+                Signal::Pin(String::from("U1"), 2),
+                true,
+                ms(3),
+                Some(ms(7)),
+            ))
+            .unwrap();
+        board
+            .inject_fault(Fault::stuck_at(
+                Signal::Trace(String::from("CLK")),
+                false,
+                ms(4),
+                Some(ms(8)),
+            ))
+            .unwrap();
+        let step = Duration::from_micros(100);
+        let mut states = vec![];
+        for _ in 0..100 {
+            board.run(step);
+            states.push(board.trace_states());
+        }
+
+        assert_eq!(board.rewind_to(ms(6)).unwrap(), ms(6));
+        assert_eq!(board.trace_states(), states[59]);
+        for expected in &states[60..] {
+            board.run(step);
+            assert_eq!(&board.trace_states(), expected);
+        }
+    }
+
+    #[test]
+    fn snapshots_keep_the_levels_and_voltages() {
+        let mut board = Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part U1 virt_ic::GateNot
+             part R virt_ic::ResistorDivider
+             net VCC PWR.1 U1.14 U1.1 R.1
+             net GND PWR.2 U1.7 U1.3 R.3
+             net Y U1.2 U1.4 U1.5
+             net OUT R.2",
+            &ChipRegistry::new(),
         )
+        .unwrap();
+        board.set_logic_model(LogicModel::StdLogic);
+        let input = board
+            .get_socket_by_name("U1")
+            .unwrap()
+            .borrow_mut()
+            .get_pin(5)
+            .unwrap();
+        let top = board
+            .get_socket_by_name("R")
+            .unwrap()
+            .borrow_mut()
+            .get_pin(1)
+            .unwrap();
+        let snapshot = board.snapshot();
+        board.run_during(Duration::from_millis(3), Duration::from_millis(1));
+        // both read as Undefined, only the level tells the conflict
+        assert_eq!(input.borrow().get_level(), crate::StdLogic::Unknown);
+        assert_eq!(top.borrow().voltage, Some(5.0));
+
+        board.restore(&snapshot).unwrap();
+        assert_eq!(input.borrow().get_level(), crate::StdLogic::HighImpedance);
+        assert_eq!(top.borrow().voltage, None);
     }
 }
@@ -0,0 +1,734 @@
+//! Analog components working with voltages
+//!
+//! Traces carry a voltage next to their digital state, see `Trace::get_voltage`:
+//! digital outputs drive `HIGH_VOLTAGE` or `LOW_VOLTAGE`, analog outputs drive any voltage
+//! and leave their digital state Undefined.
+//! Only the traces linked to an analog pin pass their voltage to their pins while running,
+//! so that digital boards don't pay for it.
+//! Digital chips don't read voltages: a `SchmittTrigger` or a `Comparator` turns them into states.
+//!
+//! A power-on reset, holding RESET Low for about 70 ms after power-up:
+//! ```
+//! use std::time::Duration;
+//! use virt_ic::{chip::ChipRegistry, Board, State};
+//!
+//! let mut board = Board::from_netlist(
+//!     "part PWR virt_ic::Generator
+//!      part RC virt_ic::Capacitor
+//!      part ST virt_ic::SchmittTrigger
+//!      net VCC PWR.1 RC.1
+//!      net GND PWR.2 RC.3
+//!      net CAP RC.2 ST.1
+//!      net RESET ST.2",
+//!     &ChipRegistry::new(),
+//! )
+//! .unwrap();
+//! let reset = board.get_trace_by_name("RESET").unwrap();
+//! board.run_during(Duration::from_millis(50), Duration::from_millis(1));
+//! assert_eq!(reset.borrow().get_state(), State::Low);
+//! board.run_during(Duration::from_millis(100), Duration::from_millis(1));
+//! assert_eq!(reset.borrow().get_state(), State::High);
+//! ```
+use super::{from_data, to_data, Chip, ChipData, ChipInfo, Pin, PinType};
+use crate::{Error, Result, State};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Voltage of a High digital output, and of a trace forced High
+pub const HIGH_VOLTAGE: f64 = 5.0;
+/// Voltage of a Low digital output, and of a trace forced Low
+pub const LOW_VOLTAGE: f64 = 0.0;
+
+/// Get the voltage a digital state drives, `None` for Undefined
+pub fn to_voltage(state: &State) -> Option<f64> {
+    match state {
+        State::High => Some(HIGH_VOLTAGE),
+        State::Low => Some(LOW_VOLTAGE),
+        State::Undefined => None,
+    }
+}
+
+/// Drive a voltage on an output pin, `None` to stop driving it
+fn drive(pin: &Rc<RefCell<Pin>>, voltage: Option<f64>) {
+    let mut pin = pin.borrow_mut();
+    pin.state = State::Undefined;
+    pin.voltage = voltage;
+}
+
+/// Create a pin reading or driving a voltage
+fn analog_pin(uuid: u128, number: u8, pin_type: PinType) -> Rc<RefCell<Pin>> {
+    let mut pin = Pin::new(uuid, number, pin_type);
+    pin.analog = true;
+    Rc::new(RefCell::new(pin))
+}
+
+fn read(pin: &Rc<RefCell<Pin>>) -> Option<f64> {
+    pin.borrow().voltage
+}
+
+/// # Two resistors in series dividing a voltage
+/// OUT = BOTTOM + (TOP - BOTTOM) * R2 / (R1 + R2), R1 being between TOP and OUT.
+/// The output isn't loaded by the chips reading it.
+///
+/// # Diagram
+/// ```text
+///           --------
+///   TOP   --|1    2|-- OUT
+///  BOTTOM --|3     |
+///           --------
+/// ```
+#[derive(Debug)]
+pub struct ResistorDivider {
+    uuid: u128,
+    pin: [Rc<RefCell<Pin>>; 3],
+    resistors: Resistors,
+}
+impl Default for ResistorDivider {
+    fn default() -> Self {
+        Self::new(10_000.0, 10_000.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Resistors {
+    r1: f64,
+    r2: f64,
+}
+
+impl ResistorDivider {
+    pub const TYPE: &'static str = "virt_ic::ResistorDivider";
+
+    pub const TOP: u8 = 1;
+    pub const OUT: u8 = 2;
+    pub const BOTTOM: u8 = 3;
+
+    /// Create a divider from its resistances in ohms, `r1` between TOP and OUT, `r2` between OUT and BOTTOM
+    pub fn new(r1: f64, r2: f64) -> Self {
+        let uuid = uuid::Uuid::new_v4().as_u128();
+        Self {
+            uuid,
+            pin: [
+                analog_pin(uuid, 1, PinType::Input),
+                analog_pin(uuid, 2, PinType::Output),
+                analog_pin(uuid, 3, PinType::Input),
+            ],
+            resistors: Resistors { r1, r2 },
+        }
+    }
+
+    /// Get the resistances in ohms
+    pub fn get_resistances(&self) -> (f64, f64) {
+        (self.resistors.r1, self.resistors.r2)
+    }
+}
+
+impl Chip for ResistorDivider {
+    fn get_uuid(&self) -> u128 {
+        self.uuid
+    }
+    fn get_type(&self) -> &str {
+        Self::TYPE
+    }
+    fn get_pin_qty(&self) -> u8 {
+        3
+    }
+
+    fn _get_pin(&mut self, pin: u8) -> Rc<RefCell<Pin>> {
+        self.pin[pin as usize - 1].clone()
+    }
+
+    fn get_info(&self) -> ChipInfo {
+        ChipInfo {
+            name: "Resistor Divider",
+            description: "Two resistors in series dividing a voltage",
+            data: format!(
+                "R1: {} ohms, R2: {} ohms",
+                self.resistors.r1, self.resistors.r2
+            ),
+        }
+    }
+
+    fn run(&mut self, _: Duration) {
+        let Resistors { r1, r2 } = self.resistors;
+        let voltage = match (read(&self.pin[0]), read(&self.pin[2])) {
+            (Some(top), Some(bottom)) if r1 + r2 > 0.0 => {
+                Some(bottom + (top - bottom) * r2 / (r1 + r2))
+            }
+            _ => None,
+        };
+        drive(&self.pin[1], voltage);
+    }
+
+    fn save_data(&self) -> Result<ChipData> {
+        to_data(&self.resistors)
+    }
+    fn load_data(&mut self, chip_data: &ChipData) -> Result<()> {
+        self.resistors = from_data(Self::TYPE, chip_data)?;
+        Ok(())
+    }
+}
+
+/// # A potentiometer
+/// WIPER = A + (B - A) * position, the position going from 0 (on A) to 1 (on B).
+/// Use `set_position` to turn it, like a knob or a sensor.
+///
+/// # Diagram
+/// ```text
+///          --------
+///    A   --|1    2|-- WIPER
+///    B   --|3     |
+///          --------
+/// ```
+#[derive(Debug)]
+pub struct Potentiometer {
+    uuid: u128,
+    pin: [Rc<RefCell<Pin>>; 3],
+    position: f64,
+}
+impl Default for Potentiometer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PotentiometerState {
+    position: f64,
+}
+
+impl Potentiometer {
+    pub const TYPE: &'static str = "virt_ic::Potentiometer";
+
+    pub const A: u8 = 1;
+    pub const WIPER: u8 = 2;
+    pub const B: u8 = 3;
+
+    /// Create a potentiometer at mid position
+    pub fn new() -> Self {
+        let uuid = uuid::Uuid::new_v4().as_u128();
+        Self {
+            uuid,
+            pin: [
+                analog_pin(uuid, 1, PinType::Input),
+                analog_pin(uuid, 2, PinType::Output),
+                analog_pin(uuid, 3, PinType::Input),
+            ],
+            position: 0.5,
+        }
+    }
+
+    /// Turn the potentiometer, the position is clamped between 0 and 1
+    pub fn set_position(&mut self, position: f64) {
+        self.position = position.clamp(0.0, 1.0);
+    }
+
+    pub fn get_position(&self) -> f64 {
+        self.position
+    }
+}
+
+impl Chip for Potentiometer {
+    fn get_uuid(&self) -> u128 {
+        self.uuid
+    }
+    fn get_type(&self) -> &str {
+        Self::TYPE
+    }
+    fn get_pin_qty(&self) -> u8 {
+        3
+    }
+
+    fn _get_pin(&mut self, pin: u8) -> Rc<RefCell<Pin>> {
+        self.pin[pin as usize - 1].clone()
+    }
+
+    fn get_info(&self) -> ChipInfo {
+        ChipInfo {
+            name: "Potentiometer",
+            description: "A variable voltage divider",
+            data: format!("Position: {:.0}%", self.position * 100.0),
+        }
+    }
+
+    fn run(&mut self, _: Duration) {
+        let voltage = match (read(&self.pin[0]), read(&self.pin[2])) {
+            (Some(a), Some(b)) => Some(a + (b - a) * self.position),
+            _ => None,
+        };
+        drive(&self.pin[1], voltage);
+    }
+
+    fn save_data(&self) -> Result<ChipData> {
+        to_data(&PotentiometerState {
+            position: self.position,
+        })
+    }
+    fn load_data(&mut self, chip_data: &ChipData) -> Result<()> {
+        let state: PotentiometerState = from_data(Self::TYPE, chip_data)?;
+        self.set_position(state.position);
+        Ok(())
+    }
+}
+
+/// # A capacitor charged through a resistor
+/// The capacitor between OUT and GND charges towards IN with the time constant R * C,
+/// over the duration given to `run`. It holds its charge while IN isn't driven.
+///
+/// # Diagram
+/// ```text
+///          --------
+///   IN   --|1    2|-- OUT
+///   GND  --|3     |
+///          --------
+/// ```
+#[derive(Debug)]
+pub struct Capacitor {
+    uuid: u128,
+    pin: [Rc<RefCell<Pin>>; 3],
+    state: CapacitorState,
+}
+impl Default for Capacitor {
+    fn default() -> Self {
+        Self::new(10_000.0, 10e-6)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct CapacitorState {
+    resistance: f64,
+    capacitance: f64,
+    /// Voltage across the capacitor
+    voltage: f64,
+}
+
+impl Capacitor {
+    pub const TYPE: &'static str = "virt_ic::Capacitor";
+
+    pub const IN: u8 = 1;
+    pub const OUT: u8 = 2;
+    pub const GND: u8 = 3;
+
+    /// Create a discharged capacitor from the series resistance in ohms and the capacitance in farads
+    pub fn new(resistance: f64, capacitance: f64) -> Self {
+        let uuid = uuid::Uuid::new_v4().as_u128();
+        Self {
+            uuid,
+            pin: [
+                analog_pin(uuid, 1, PinType::Input),
+                analog_pin(uuid, 2, PinType::Output),
+                analog_pin(uuid, 3, PinType::Input),
+            ],
+            state: CapacitorState {
+                resistance,
+                capacitance,
+                voltage: 0.0,
+            },
+        }
+    }
+
+    /// Get the time constant R * C
+    pub fn get_time_constant(&self) -> Duration {
+        Duration::from_secs_f64((self.state.resistance * self.state.capacitance).max(0.0))
+    }
+
+    /// Get the voltage across the capacitor
+    pub fn get_voltage(&self) -> f64 {
+        self.state.voltage
+    }
+
+    /// Charge the capacitor to a voltage
+    pub fn set_voltage(&mut self, voltage: f64) {
+        self.state.voltage = voltage;
+    }
+}
+
+impl Chip for Capacitor {
+    fn get_uuid(&self) -> u128 {
+        self.uuid
+    }
+    fn get_type(&self) -> &str {
+        Self::TYPE
+    }
+    fn get_pin_qty(&self) -> u8 {
+        3
+    }
+
+    fn _get_pin(&mut self, pin: u8) -> Rc<RefCell<Pin>> {
+        self.pin[pin as usize - 1].clone()
+    }
+
+    fn get_info(&self) -> ChipInfo {
+        ChipInfo {
+            name: "Capacitor",
+            description: "A capacitor charged through a resistor",
+            data: format!("Voltage: {:.3} V", self.state.voltage),
+        }
+    }
+
+    fn run(&mut self, elapsed_time: Duration) {
+        let ground = read(&self.pin[2]).unwrap_or(LOW_VOLTAGE);
+        if let Some(input) = read(&self.pin[0]) {
+            let time_constant = self.state.resistance * self.state.capacitance;
+            let target = input - ground;
+            if time_constant > 0.0 {
+                let ratio = 1.0 - (-elapsed_time.as_secs_f64() / time_constant).exp();
+                self.state.voltage += (target - self.state.voltage) * ratio;
+            } else {
+                self.state.voltage = target;
+            }
+        }
+        drive(&self.pin[1], Some(ground + self.state.voltage));
+    }
+
+    fn save_data(&self) -> Result<ChipData> {
+        to_data(&self.state)
+    }
+    fn load_data(&mut self, chip_data: &ChipData) -> Result<()> {
+        self.state = from_data(Self::TYPE, chip_data)?;
+        Ok(())
+    }
+    fn snapshot(&self) -> Box<dyn Any> {
+        Box::new(self.state)
+    }
+    fn restore(&mut self, snapshot: &dyn Any) -> Result<()> {
+        self.state = *snapshot
+            .downcast_ref::<CapacitorState>()
+            .ok_or_else(|| Error::invalid_snapshot(Self::TYPE))?;
+        Ok(())
+    }
+}
+
+/// # A Schmitt trigger turning a voltage into a digital state
+/// OUT goes High when IN rises above the high threshold, Low when it falls below the low threshold,
+/// and keeps its state in between. It's Undefined while IN isn't driven.
+///
+/// # Diagram
+/// ```text
+///          --------
+///   IN   --|1    2|-- OUT
+///          --------
+/// ```
+#[derive(Debug)]
+pub struct SchmittTrigger {
+    uuid: u128,
+    pin: [Rc<RefCell<Pin>>; 2],
+    state: TriggerState,
+}
+impl Default for SchmittTrigger {
+    fn default() -> Self {
+        Self::new(HIGH_VOLTAGE / 3.0, HIGH_VOLTAGE * 2.0 / 3.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct TriggerState {
+    low_threshold: f64,
+    high_threshold: f64,
+    high: bool,
+}
+
+impl SchmittTrigger {
+    pub const TYPE: &'static str = "virt_ic::SchmittTrigger";
+
+    pub const IN: u8 = 1;
+    pub const OUT: u8 = 2;
+
+    /// Create a trigger from its thresholds in volts
+    pub fn new(low_threshold: f64, high_threshold: f64) -> Self {
+        let uuid = uuid::Uuid::new_v4().as_u128();
+        Self {
+            uuid,
+            pin: [
+                analog_pin(uuid, 1, PinType::Input),
+                Rc::new(RefCell::new(Pin::new(uuid, 2, PinType::Output))),
+            ],
+            state: TriggerState {
+                low_threshold,
+                high_threshold,
+                high: false,
+            },
+        }
+    }
+
+    /// Get the low and high thresholds in volts
+    pub fn get_thresholds(&self) -> (f64, f64) {
+        (self.state.low_threshold, self.state.high_threshold)
+    }
+}
+
+impl Chip for SchmittTrigger {
+    fn get_uuid(&self) -> u128 {
+        self.uuid
+    }
+    fn get_type(&self) -> &str {
+        Self::TYPE
+    }
+    fn get_pin_qty(&self) -> u8 {
+        2
+    }
+
+    fn _get_pin(&mut self, pin: u8) -> Rc<RefCell<Pin>> {
+        self.pin[pin as usize - 1].clone()
+    }
+
+    fn get_info(&self) -> ChipInfo {
+        ChipInfo {
+            name: "Schmitt Trigger",
+            description: "Turns a voltage into a digital state with hysteresis",
+            data: format!(
+                "Thresholds: {} V / {} V",
+                self.state.low_threshold, self.state.high_threshold
+            ),
+        }
+    }
+
+    fn run(&mut self, _: Duration) {
+        self.pin[1].borrow_mut().state = match read(&self.pin[0]) {
+            Some(voltage) => {
+                if voltage >= self.state.high_threshold {
+                    self.state.high = true;
+                } else if voltage <= self.state.low_threshold {
+                    self.state.high = false;
+                }
+                State::from(self.state.high)
+            }
+            None => State::Undefined,
+        };
+    }
+
+    fn save_data(&self) -> Result<ChipData> {
+        to_data(&self.state)
+    }
+    fn load_data(&mut self, chip_data: &ChipData) -> Result<()> {
+        self.state = from_data(Self::TYPE, chip_data)?;
+        Ok(())
+    }
+    fn snapshot(&self) -> Box<dyn Any> {
+        Box::new(self.state)
+    }
+    fn restore(&mut self, snapshot: &dyn Any) -> Result<()> {
+        self.state = *snapshot
+            .downcast_ref::<TriggerState>()
+            .ok_or_else(|| Error::invalid_snapshot(Self::TYPE))?;
+        Ok(())
+    }
+}
+
+/// # A voltage comparator
+/// OUT is High when PLUS is above MINUS, Low otherwise, and Undefined while one of them isn't driven.
+/// With a potentiometer on MINUS, it turns a sensor voltage on PLUS into a digital signal.
+///
+/// # Diagram
+/// ```text
+///          --------
+///  PLUS  --|1    3|-- OUT
+///  MINUS --|2     |
+///          --------
+/// ```
+#[derive(Debug)]
+pub struct Comparator {
+    uuid: u128,
+    pin: [Rc<RefCell<Pin>>; 3],
+}
+impl Default for Comparator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Comparator {
+    pub const TYPE: &'static str = "virt_ic::Comparator";
+
+    pub const PLUS: u8 = 1;
+    pub const MINUS: u8 = 2;
+    pub const OUT: u8 = 3;
+
+    pub fn new() -> Self {
+        let uuid = uuid::Uuid::new_v4().as_u128();
+        Self {
+            uuid,
+            pin: [
+                analog_pin(uuid, 1, PinType::Input),
+                analog_pin(uuid, 2, PinType::Input),
+                Rc::new(RefCell::new(Pin::new(uuid, 3, PinType::Output))),
+            ],
+        }
+    }
+}
+
+impl Chip for Comparator {
+    fn get_uuid(&self) -> u128 {
+        self.uuid
+    }
+    fn get_type(&self) -> &str {
+        Self::TYPE
+    }
+    fn get_pin_qty(&self) -> u8 {
+        3
+    }
+
+    fn _get_pin(&mut self, pin: u8) -> Rc<RefCell<Pin>> {
+        self.pin[pin as usize - 1].clone()
+    }
+
+    fn get_info(&self) -> ChipInfo {
+        ChipInfo {
+            name: "Comparator",
+            description: "Compares two voltages",
+            data: String::new(),
+        }
+    }
+
+    fn run(&mut self, _: Duration) {
+        self.pin[2].borrow_mut().state = match (read(&self.pin[0]), read(&self.pin[1])) {
+            (Some(plus), Some(minus)) => State::from(plus > minus),
+            _ => State::Undefined,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chip::ChipRegistry, Board};
+
+    #[test]
+    fn only_analog_traces_pass_voltages() {
+        let mut board = Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part D virt_ic::ResistorDivider
+             part U1 virt_ic::GateNot
+             net VCC PWR.1 D.1 U1.14
+             net GND PWR.2 D.3 U1.7
+             net MID D.2
+             net A U1.1
+             net Y U1.2",
+            &ChipRegistry::new(),
+        )
+        .unwrap();
+        board.run(Duration::from_millis(1));
+        board.run(Duration::from_millis(1));
+        let mid = board.get_trace_by_name("MID").unwrap();
+        assert_eq!(mid.borrow().get_voltage(), Some(2.5));
+        let gate = board.get_sockets()[2].clone();
+        let input = gate.borrow_mut().get_pin(1).unwrap();
+        assert_eq!(input.borrow().voltage, None);
+        let divider = board.get_sockets()[1].clone();
+        let top = divider.borrow_mut().get_pin(ResistorDivider::TOP).unwrap();
+        assert_eq!(top.borrow().voltage, Some(HIGH_VOLTAGE));
+    }
+
+    fn set(chip: &mut dyn Chip, pin: u8, voltage: Option<f64>) {
+        chip.get_pin(pin).unwrap().borrow_mut().voltage = voltage;
+    }
+
+    fn voltage(chip: &mut dyn Chip, pin: u8) -> Option<f64> {
+        chip.get_pin(pin).unwrap().borrow().voltage
+    }
+
+    #[test]
+    fn capacitors_charge_exponentially() {
+        for step in [1, 10, 100] {
+            // a time constant of 1 s
+            let mut capacitor = Capacitor::new(1000.0, 1e-3);
+            set(&mut capacitor, Capacitor::IN, Some(HIGH_VOLTAGE));
+            set(&mut capacitor, Capacitor::GND, Some(LOW_VOLTAGE));
+            for seconds in 1..=3 {
+                for _ in 0..1000 / step {
+                    capacitor.run(Duration::from_millis(step));
+                }
+                let expected = HIGH_VOLTAGE * (1.0 - (-seconds as f64).exp());
+                assert!(
+                    (capacitor.get_voltage() - expected).abs() < 1e-9,
+                    "{} V after {} s by steps of {} ms, expected {} V",
+                    capacitor.get_voltage(),
+                    seconds,
+                    step,
+                    expected
+                );
+                assert_eq!(
+                    voltage(&mut capacitor, Capacitor::OUT),
+                    Some(capacitor.get_voltage())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn capacitors_hold_their_charge_without_input() {
+        let mut capacitor = Capacitor::new(1000.0, 1e-3);
+        capacitor.set_voltage(3.0);
+        set(&mut capacitor, Capacitor::GND, Some(1.0));
+        capacitor.run(Duration::from_secs(10));
+        assert_eq!(capacitor.get_voltage(), 3.0);
+        assert_eq!(voltage(&mut capacitor, Capacitor::OUT), Some(4.0));
+        // then it discharges towards IN
+        set(&mut capacitor, Capacitor::IN, Some(1.0));
+        capacitor.run(Duration::from_secs(10));
+        assert!(capacitor.get_voltage() < 1e-3);
+    }
+
+    #[test]
+    fn schmitt_triggers_have_hysteresis() {
+        let mut trigger = SchmittTrigger::new(1.5, 3.5);
+        let mut run = |input: Option<f64>| {
+            set(&mut trigger, SchmittTrigger::IN, input);
+            trigger.run(Duration::from_millis(1));
+            trigger.get_pin_state(SchmittTrigger::OUT)
+        };
+        assert_eq!(run(None), State::Undefined);
+        // rising ramp, from 0 V to 5 V by 0.25 V
+        for step in 0..=20 {
+            let input = step as f64 * 0.25;
+            let expected = State::from(input >= 3.5);
+            assert_eq!(run(Some(input)), expected, "{} V rising", input);
+        }
+        // falling ramp
+        for step in (0..=20).rev() {
+            let input = step as f64 * 0.25;
+            let expected = State::from(input > 1.5);
+            assert_eq!(run(Some(input)), expected, "{} V falling", input);
+        }
+    }
+
+    #[test]
+    fn comparators_compare_their_inputs() {
+        let mut comparator = Comparator::new();
+        let mut compare = |plus: Option<f64>, minus: Option<f64>| {
+            set(&mut comparator, Comparator::PLUS, plus);
+            set(&mut comparator, Comparator::MINUS, minus);
+            comparator.run(Duration::from_millis(1));
+            comparator.get_pin_state(Comparator::OUT)
+        };
+        assert_eq!(compare(Some(2.6), Some(2.5)), State::High);
+        assert_eq!(compare(Some(2.4), Some(2.5)), State::Low);
+        assert_eq!(compare(Some(2.5), Some(2.5)), State::Low);
+        assert_eq!(compare(None, Some(2.5)), State::Undefined);
+        assert_eq!(compare(Some(2.5), None), State::Undefined);
+    }
+
+    #[test]
+    fn potentiometers_follow_their_position() {
+        let mut potentiometer = Potentiometer::new();
+        set(&mut potentiometer, Potentiometer::A, Some(1.0));
+        set(&mut potentiometer, Potentiometer::B, Some(5.0));
+        let mut wiper = |position: f64| {
+            potentiometer.set_position(position);
+            potentiometer.run(Duration::from_millis(1));
+            (
+                potentiometer.get_position(),
+                voltage(&mut potentiometer, Potentiometer::WIPER),
+            )
+        };
+        assert_eq!(wiper(0.5), (0.5, Some(3.0)));
+        assert_eq!(wiper(0.25), (0.25, Some(2.0)));
+        assert_eq!(wiper(1.5), (1.0, Some(5.0)));
+        assert_eq!(wiper(-1.0), (0.0, Some(1.0)));
+        set(&mut potentiometer, Potentiometer::B, None);
+        potentiometer.run(Duration::from_millis(1));
+        assert_eq!(voltage(&mut potentiometer, Potentiometer::WIPER), None);
+    }
+}
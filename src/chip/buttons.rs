@@ -1,6 +1,8 @@
 //! Buttons and other physically interactable chips
-use super::{Chip, ChipInfo, Pin, PinType};
-use crate::State;
+use super::{from_data, legacy_data, to_data, Chip, ChipData, ChipInfo, Pin, PinType};
+use crate::{Error, Result, State};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -52,6 +54,11 @@ impl Button {
         self.down = false;
     }
 }
+#[derive(Serialize, Deserialize)]
+struct ButtonState {
+    down: bool,
+}
+
 impl Chip for Button {
     fn get_uuid(&self) -> u128 {
         self.uuid
@@ -82,10 +89,36 @@ impl Chip for Button {
         }
     }
 
-    fn save_data(&self) -> Vec<String> {
-        vec![String::from(if self.down { "DOWN" } else { "UP" })]
+    fn data_version(&self) -> u32 {
+        1
+    }
+    fn migrate_data(&self, from_version: u32, chip_data: ChipData) -> Option<ChipData> {
+        match from_version {
+            0 => {
+                let legacy = legacy_data(chip_data)?;
+                to_data(&ButtonState {
+                    down: legacy.first()? == "DOWN",
+                })
+                .ok()
+            }
+            _ => None,
+        }
+    }
+    fn save_data(&self) -> Result<ChipData> {
+        to_data(&ButtonState { down: self.down })
+    }
+    fn load_data(&mut self, chip_data: &ChipData) -> Result<()> {
+        let state: ButtonState = from_data(Self::TYPE, chip_data)?;
+        self.down = state.down;
+        Ok(())
+    }
+    fn snapshot(&self) -> Box<dyn Any> {
+        Box::new(self.down)
     }
-    fn load_data(&mut self, chip_data: &[String]) {
-        self.down = chip_data[0] == "DOWN";
+    fn restore(&mut self, snapshot: &dyn Any) -> Result<()> {
+        self.down = *snapshot
+            .downcast_ref::<bool>()
+            .ok_or_else(|| Error::invalid_snapshot(Self::TYPE))?;
+        Ok(())
     }
 }
@@ -1,10 +1,29 @@
 //! Clocks that pulse at different speeds
-use super::{Chip, ChipInfo, Pin, PinType};
-use crate::State;
+use super::{from_data, legacy_data, to_data, Chip, ChipData, ChipInfo, Pin, PinType};
+use crate::{Error, Result, State};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::time::Duration;
 
+/// State shared by the clocks
+#[derive(Serialize, Deserialize)]
+struct ClockState {
+    active: bool,
+    timer: Duration,
+}
+
+/// Convert the `["ON", "(secs:0,nanos:0)"]` data saved before `data_version` 1
+fn migrate_legacy(chip_data: ChipData) -> Option<ChipData> {
+    let legacy = legacy_data(chip_data)?;
+    to_data(&ClockState {
+        active: legacy.first()? == "ON",
+        timer: ron::from_str(legacy.get(1)?).ok()?,
+    })
+    .ok()
+}
+
 /// A 100 Hz simple clock
 /// CLK: clock
 /// ```
@@ -92,16 +111,37 @@ impl Chip for Clock100Hz {
         }
     }
 
-    fn save_data(&self) -> Vec<String> {
-        vec![
-            String::from(if self.active { "ON" } else { "OFF" }),
-            ron::to_string(&self.timer).unwrap(),
-        ]
+    fn data_version(&self) -> u32 {
+        1
+    }
+    fn migrate_data(&self, from_version: u32, chip_data: ChipData) -> Option<ChipData> {
+        match from_version {
+            0 => migrate_legacy(chip_data),
+            _ => None,
+        }
     }
-    fn load_data(&mut self, chip_data: &[String]) {
-        let timer: Duration = ron::from_str(&chip_data[1]).unwrap();
-        self.active = chip_data[0] == "ON";
-        self.timer = timer;
+    fn save_data(&self) -> Result<ChipData> {
+        to_data(&ClockState {
+            active: self.active,
+            timer: self.timer,
+        })
+    }
+    fn load_data(&mut self, chip_data: &ChipData) -> Result<()> {
+        let state: ClockState = from_data(Self::TYPE, chip_data)?;
+        self.active = state.active;
+        self.timer = state.timer;
+        Ok(())
+    }
+    fn snapshot(&self) -> Box<dyn Any> {
+        Box::new((self.timer, self.active))
+    }
+    fn restore(&mut self, snapshot: &dyn Any) -> Result<()> {
+        let (timer, active) = snapshot
+            .downcast_ref::<(Duration, bool)>()
+            .ok_or_else(|| Error::invalid_snapshot(Self::TYPE))?;
+        self.timer = *timer;
+        self.active = *active;
+        Ok(())
     }
 }
 
@@ -192,15 +232,36 @@ impl Chip for Clock1kHz {
         }
     }
 
-    fn save_data(&self) -> Vec<String> {
-        vec![
-            String::from(if self.active { "ON" } else { "OFF" }),
-            ron::to_string(&self.timer).unwrap(),
-        ]
+    fn data_version(&self) -> u32 {
+        1
+    }
+    fn migrate_data(&self, from_version: u32, chip_data: ChipData) -> Option<ChipData> {
+        match from_version {
+            0 => migrate_legacy(chip_data),
+            _ => None,
+        }
+    }
+    fn save_data(&self) -> Result<ChipData> {
+        to_data(&ClockState {
+            active: self.active,
+            timer: self.timer,
+        })
+    }
+    fn load_data(&mut self, chip_data: &ChipData) -> Result<()> {
+        let state: ClockState = from_data(Self::TYPE, chip_data)?;
+        self.active = state.active;
+        self.timer = state.timer;
+        Ok(())
+    }
+    fn snapshot(&self) -> Box<dyn Any> {
+        Box::new((self.timer, self.active))
     }
-    fn load_data(&mut self, chip_data: &[String]) {
-        let timer: Duration = ron::from_str(&chip_data[1]).unwrap();
-        self.active = chip_data[0] == "ON";
-        self.timer = timer;
+    fn restore(&mut self, snapshot: &dyn Any) -> Result<()> {
+        let (timer, active) = snapshot
+            .downcast_ref::<(Duration, bool)>()
+            .ok_or_else(|| Error::invalid_snapshot(Self::TYPE))?;
+        self.timer = *timer;
+        self.active = *active;
+        Ok(())
     }
 }
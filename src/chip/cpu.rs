@@ -1,6 +1,8 @@
 //! Central Processing Units
-use super::{Chip, ChipInfo, Pin, PinType};
-use crate::State;
+use super::{from_data, legacy_data, to_data, Chip, ChipData, ChipInfo, Pin, PinType};
+use crate::{Error, Result, State};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -932,6 +934,78 @@ impl SimpleCPU {
         }
     }
 }
+/// Registers, flags, execution and microcode state of the CPU
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct CpuState {
+    accumulator: u8,
+    reg_b: u8,
+    reg_c: u8,
+    reg_h: u8,
+    reg_l: u8,
+    flag_zero: bool,
+    flag_neg: bool,
+    flag_carry: bool,
+    flag_overflow: bool,
+    program_counter: u16,
+    stack_bank: u8,
+    stack_pointer: u8,
+    current_opcode: u8,
+    param_first: u8,
+    param_second: u8,
+    microcode_state: u8,
+    executing: bool,
+    initializing: bool,
+    halted: bool,
+}
+
+impl SimpleCPU {
+    fn get_state(&self) -> CpuState {
+        CpuState {
+            accumulator: self.accumulator,
+            reg_b: self.reg_b,
+            reg_c: self.reg_c,
+            reg_h: self.reg_h,
+            reg_l: self.reg_l,
+            flag_zero: self.flag_zero,
+            flag_neg: self.flag_neg,
+            flag_carry: self.flag_carry,
+            flag_overflow: self.flag_overflow,
+            program_counter: self.program_counter,
+            stack_bank: self.stack_bank,
+            stack_pointer: self.stack_pointer,
+            current_opcode: self.current_opcode,
+            param_first: self.param_first,
+            param_second: self.param_second,
+            microcode_state: self.microcode_state,
+            executing: self.executing,
+            initializing: self.initializing,
+            halted: self.halted,
+        }
+    }
+
+    fn set_state(&mut self, state: CpuState) {
+        self.accumulator = state.accumulator;
+        self.reg_b = state.reg_b;
+        self.reg_c = state.reg_c;
+        self.reg_h = state.reg_h;
+        self.reg_l = state.reg_l;
+        self.flag_zero = state.flag_zero;
+        self.flag_neg = state.flag_neg;
+        self.flag_carry = state.flag_carry;
+        self.flag_overflow = state.flag_overflow;
+        self.program_counter = state.program_counter;
+        self.stack_bank = state.stack_bank;
+        self.stack_pointer = state.stack_pointer;
+        self.current_opcode = state.current_opcode;
+        self.param_first = state.param_first;
+        self.param_second = state.param_second;
+        self.microcode_state = state.microcode_state;
+        self.executing = state.executing;
+        self.initializing = state.initializing;
+        self.halted = state.halted;
+    }
+}
+
 impl Chip for SimpleCPU {
     fn get_uuid(&self) -> u128 {
         self.uuid
@@ -1033,65 +1107,59 @@ impl Chip for SimpleCPU {
         }
     }
 
-    fn save_data(&self) -> Vec<String> {
-        vec![
-            ron::to_string(&(
-                self.accumulator,
-                self.reg_b,
-                self.reg_c,
-                self.reg_h,
-                self.reg_l,
-            ))
-            .unwrap(),
-            ron::to_string(&(
-                self.flag_zero,
-                self.flag_neg,
-                self.flag_carry,
-                self.flag_overflow,
-            ))
-            .unwrap(),
-            ron::to_string(&(
-                self.program_counter,
-                self.stack_bank,
-                self.stack_pointer,
-                self.current_opcode,
-                self.param_first,
-                self.param_second,
-            ))
-            .unwrap(),
-            ron::to_string(&(
-                self.microcode_state,
-                self.executing,
-                self.initializing,
-                self.halted,
-            ))
-            .unwrap(),
-        ]
+    fn data_version(&self) -> u32 {
+        1
     }
-    fn load_data(&mut self, chip_data: &[String]) {
-        let registers: (u8, u8, u8, u8, u8) = ron::from_str(&chip_data[0]).unwrap();
-        let flags: (bool, bool, bool, bool) = ron::from_str(&chip_data[1]).unwrap();
-        let exec: (u16, u8, u8, u8, u8, u8) = ron::from_str(&chip_data[2]).unwrap();
-        let internal: (u8, bool, bool, bool) = ron::from_str(&chip_data[3]).unwrap();
-
-        self.accumulator = registers.0;
-        self.reg_b = registers.1;
-        self.reg_c = registers.2;
-        self.reg_h = registers.3;
-        self.reg_l = registers.4;
-        self.flag_zero = flags.0;
-        self.flag_neg = flags.1;
-        self.flag_carry = flags.2;
-        self.flag_overflow = flags.3;
-        self.program_counter = exec.0;
-        self.stack_bank = exec.1;
-        self.stack_pointer = exec.2;
-        self.current_opcode = exec.3;
-        self.param_first = exec.4;
-        self.param_second = exec.5;
-        self.microcode_state = internal.0;
-        self.executing = internal.1;
-        self.initializing = internal.2;
-        self.halted = internal.3;
+    fn migrate_data(&self, from_version: u32, chip_data: ChipData) -> Option<ChipData> {
+        match from_version {
+            0 => {
+                // registers, flags, execution and internal state as RON tuples
+                let legacy = legacy_data(chip_data)?;
+                let registers: (u8, u8, u8, u8, u8) = ron::from_str(legacy.first()?).ok()?;
+                let flags: (bool, bool, bool, bool) = ron::from_str(legacy.get(1)?).ok()?;
+                let exec: (u16, u8, u8, u8, u8, u8) = ron::from_str(legacy.get(2)?).ok()?;
+                let internal: (u8, bool, bool, bool) = ron::from_str(legacy.get(3)?).ok()?;
+                to_data(&CpuState {
+                    accumulator: registers.0,
+                    reg_b: registers.1,
+                    reg_c: registers.2,
+                    reg_h: registers.3,
+                    reg_l: registers.4,
+                    flag_zero: flags.0,
+                    flag_neg: flags.1,
+                    flag_carry: flags.2,
+                    flag_overflow: flags.3,
+                    program_counter: exec.0,
+                    stack_bank: exec.1,
+                    stack_pointer: exec.2,
+                    current_opcode: exec.3,
+                    param_first: exec.4,
+                    param_second: exec.5,
+                    microcode_state: internal.0,
+                    executing: internal.1,
+                    initializing: internal.2,
+                    halted: internal.3,
+                })
+                .ok()
+            }
+            _ => None,
+        }
+    }
+    fn save_data(&self) -> Result<ChipData> {
+        to_data(&self.get_state())
+    }
+    fn load_data(&mut self, chip_data: &ChipData) -> Result<()> {
+        self.set_state(from_data(Self::TYPE, chip_data)?);
+        Ok(())
+    }
+    fn snapshot(&self) -> Box<dyn Any> {
+        Box::new(self.get_state())
+    }
+    fn restore(&mut self, snapshot: &dyn Any) -> Result<()> {
+        let state = snapshot
+            .downcast_ref::<CpuState>()
+            .ok_or_else(|| Error::invalid_snapshot(Self::TYPE))?;
+        self.set_state(*state);
+        Ok(())
     }
 }
@@ -1,9 +1,156 @@
 //! Logic Gates like OR, AND, NOT ...
 use super::{Chip, ChipInfo, Pin, PinType};
-use crate::State;
+use crate::{LogicModel, State, StdLogic};
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// The logic function of a single gate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GateKind {
+    And,
+    Or,
+    Not,
+    Nand,
+    Nor,
+}
+
+impl GateKind {
+    /// Compute the output of the gate from its inputs, High being true
+    pub fn eval<I: IntoIterator<Item = bool>>(self, inputs: I) -> bool {
+        let mut inputs = inputs.into_iter();
+        match self {
+            Self::And => inputs.all(|input| input),
+            Self::Nand | Self::Not => !inputs.all(|input| input),
+            Self::Or => inputs.any(|input| input),
+            Self::Nor => !inputs.any(|input| input),
+        }
+    }
+
+    /// Compute the output of the gate from the levels of its inputs, with the IEEE 1164 tables
+    pub fn eval_level<I: IntoIterator<Item = StdLogic>>(self, inputs: I) -> StdLogic {
+        let inputs = inputs.into_iter();
+        match self {
+            Self::And => inputs.fold(StdLogic::High, |output, input| output & input),
+            Self::Nand | Self::Not => !inputs.fold(StdLogic::High, |output, input| output & input),
+            Self::Or => inputs.fold(StdLogic::Low, |output, input| output | input),
+            Self::Nor => !inputs.fold(StdLogic::Low, |output, input| output | input),
+        }
+    }
+
+    /// Compute the output of the gate from the states of its inputs, propagating Undefined:
+    /// a Low input of an AND or a High input of an OR decides the output, Undefined ones give Undefined otherwise
+    pub fn eval_state<I: IntoIterator<Item = State>>(self, inputs: I) -> State {
+        State::from(self.eval_level(inputs.into_iter().map(StdLogic::from)))
+    }
+}
+
+/// A single gate bundled in a gate chip, with its input pins and output pin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gate {
+    pub kind: GateKind,
+    pub inputs: &'static [u8],
+    pub output: u8,
+}
+
+/// Get the gates bundled in a built-in gate chip from its chip type
+///
+/// Returns `None` if the chip type isn't a built-in gate chip.
+/// Every built-in gate chip is powered through pin 14 (VCC) and pin 7 (GND).
+pub fn get_gates(chip_type: &str) -> Option<&'static [Gate]> {
+    match chip_type {
+        GateOr::TYPE => Some(&GateOr::GATES),
+        GateAnd::TYPE => Some(&GateAnd::GATES),
+        Gate3InputAnd::TYPE => Some(&Gate3InputAnd::GATES),
+        GateNot::TYPE => Some(&GateNot::GATES),
+        GateNor::TYPE => Some(&GateNor::GATES),
+        Gate3InputNor::TYPE => Some(&Gate3InputNor::GATES),
+        GateNand::TYPE => Some(&GateNand::GATES),
+        Gate3InputNand::TYPE => Some(&Gate3InputNand::GATES),
+        _ => None,
+    }
+}
+
+/// How a built-in gate chip reads its inputs and drives its pins with the binary model,
+/// which differs between chips
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Quirks {
+    /// State of all the pins when the chip isn't powered
+    pub(crate) unpowered: State,
+    /// Undefined inputs are read as High instead of Low
+    pub(crate) undefined_as_high: bool,
+}
+
+/// Get the quirks of a built-in gate chip from its chip type
+pub(crate) fn get_quirks(chip_type: &str) -> Quirks {
+    Quirks {
+        // the OR and NOR chips pull their pins Low, the other ones leave them Undefined
+        unpowered: match chip_type {
+            GateOr::TYPE | GateNor::TYPE | Gate3InputNor::TYPE => State::Low,
+            _ => State::Undefined,
+        },
+        // the 3-input NOR outputs High only if its inputs are all Low
+        undefined_as_high: chip_type == Gate3InputNor::TYPE,
+    }
+}
+
+/// Every built-in gate chip is powered through these pins
+const VCC: usize = 14;
+const GND: usize = 7;
+
+/// Run the gates of a built-in gate chip
+/// When the chip isn't powered, all its pins are set to its unpowered state.
+fn run_gates(pins: &[Rc<RefCell<Pin>>; 14], gates: &[Gate], quirks: Quirks, model: LogicModel) {
+    let pin = |number: u8| &pins[number as usize - 1];
+    match model {
+        LogicModel::Binary | LogicModel::XPropagation => {
+            if pins[GND - 1].borrow().state == State::Low
+                && pins[VCC - 1].borrow().state == State::High
+            {
+                let read = |state: &State| match state {
+                    State::High => true,
+                    State::Low => false,
+                    State::Undefined => quirks.undefined_as_high,
+                };
+                for gate in gates {
+                    let inputs = gate
+                        .inputs
+                        .iter()
+                        .map(|input| pin(*input).borrow().state.clone());
+                    let output = if model == LogicModel::XPropagation {
+                        gate.kind.eval_state(inputs)
+                    } else {
+                        State::from(gate.kind.eval(inputs.map(|state| read(&state))))
+                    };
+                    pin(gate.output).borrow_mut().state = output;
+                }
+            } else {
+                for pin in pins {
+                    pin.borrow_mut().state = quirks.unpowered.clone();
+                }
+            }
+        }
+        LogicModel::StdLogic => {
+            if pins[GND - 1].borrow().get_level().to_x01() == StdLogic::Low
+                && pins[VCC - 1].borrow().get_level().to_x01() == StdLogic::High
+            {
+                for gate in gates {
+                    let output = gate.kind.eval_level(
+                        gate.inputs
+                            .iter()
+                            .map(|input| pin(*input).borrow().get_level()),
+                    );
+                    pin(gate.output).borrow_mut().set_level(output);
+                }
+            } else {
+                let level = StdLogic::from(quirks.unpowered);
+                for pin in pins {
+                    pin.borrow_mut().set_level(level);
+                }
+            }
+        }
+    }
+}
+
 /// # A chip with 4 bundled "OR" gates
 ///
 /// # Diagram
@@ -22,6 +169,7 @@ use std::rc::Rc;
 pub struct GateOr {
     uuid: u128,
     pin: [Rc<RefCell<Pin>>; 14],
+    model: LogicModel,
 }
 impl Default for GateOr {
     fn default() -> Self {
@@ -47,6 +195,30 @@ impl GateOr {
     pub const VCC: u8 = 14;
     pub const GND: u8 = 7;
 
+    /// Gates bundled in the chip
+    pub const GATES: [Gate; 4] = [
+        Gate {
+            kind: GateKind::Or,
+            inputs: &[Self::A, Self::B],
+            output: Self::A_OR_B,
+        },
+        Gate {
+            kind: GateKind::Or,
+            inputs: &[Self::C, Self::D],
+            output: Self::C_OR_D,
+        },
+        Gate {
+            kind: GateKind::Or,
+            inputs: &[Self::E, Self::F],
+            output: Self::E_OR_F,
+        },
+        Gate {
+            kind: GateKind::Or,
+            inputs: &[Self::G, Self::H],
+            output: Self::G_OR_H,
+        },
+    ];
+
     pub fn new() -> Self {
         let uuid = uuid::Uuid::new_v4().as_u128();
         Self {
@@ -67,6 +239,7 @@ impl GateOr {
                 Rc::new(RefCell::new(Pin::new(uuid, 13, PinType::Input))),
                 Rc::new(RefCell::new(Pin::new(uuid, 14, PinType::Input))),
             ],
+            model: LogicModel::Binary,
         }
     }
 }
@@ -94,46 +267,11 @@ impl Chip for GateOr {
     }
 
     fn run(&mut self, _: std::time::Duration) {
-        // check alimented
-        if self.pin[6].borrow().state == State::Low && self.pin[13].borrow().state == State::High {
-            // A && B
-            self.pin[2].borrow_mut().state = if self.pin[0].borrow().state == State::High
-                || self.pin[1].borrow().state == State::High
-            {
-                State::High
-            } else {
-                State::Low
-            };
-            // C && D
-            self.pin[5].borrow_mut().state = if self.pin[3].borrow().state == State::High
-                || self.pin[4].borrow().state == State::High
-            {
-                State::High
-            } else {
-                State::Low
-            };
-            // E && F
-            self.pin[10].borrow_mut().state = if self.pin[11].borrow().state == State::High
-                || self.pin[12].borrow().state == State::High
-            {
-                State::High
-            } else {
-                State::Low
-            };
-            // G && H
-            self.pin[7].borrow_mut().state = if self.pin[8].borrow().state == State::High
-                || self.pin[9].borrow().state == State::High
-            {
-                State::High
-            } else {
-                State::Low
-            };
-        } else {
-            // turn off every pin
-            for i in 0..14 {
-                self.pin[i].borrow_mut().state = State::Low;
-            }
-        }
+        run_gates(&self.pin, &Self::GATES, get_quirks(Self::TYPE), self.model);
+    }
+
+    fn set_logic_model(&mut self, model: LogicModel) {
+        self.model = model;
     }
 }
 
@@ -155,6 +293,7 @@ impl Chip for GateOr {
 pub struct GateAnd {
     uuid: u128,
     pin: [Rc<RefCell<Pin>>; 14],
+    model: LogicModel,
 }
 impl Default for GateAnd {
     fn default() -> Self {
@@ -180,6 +319,30 @@ impl GateAnd {
     pub const VCC: u8 = 14;
     pub const GND: u8 = 7;
 
+    /// Gates bundled in the chip
+    pub const GATES: [Gate; 4] = [
+        Gate {
+            kind: GateKind::And,
+            inputs: &[Self::A, Self::B],
+            output: Self::A_AND_B,
+        },
+        Gate {
+            kind: GateKind::And,
+            inputs: &[Self::C, Self::D],
+            output: Self::C_AND_D,
+        },
+        Gate {
+            kind: GateKind::And,
+            inputs: &[Self::E, Self::F],
+            output: Self::E_AND_F,
+        },
+        Gate {
+            kind: GateKind::And,
+            inputs: &[Self::G, Self::H],
+            output: Self::G_AND_H,
+        },
+    ];
+
     pub fn new() -> Self {
         let uuid = uuid::Uuid::new_v4().as_u128();
         Self {
@@ -200,6 +363,7 @@ impl GateAnd {
                 Rc::new(RefCell::new(Pin::new(uuid, 13, PinType::Input))),
                 Rc::new(RefCell::new(Pin::new(uuid, 14, PinType::Input))),
             ],
+            model: LogicModel::Binary,
         }
     }
 }
@@ -227,46 +391,11 @@ impl Chip for GateAnd {
     }
 
     fn run(&mut self, _: std::time::Duration) {
-        // check alimented
-        if self.pin[6].borrow().state == State::Low && self.pin[13].borrow().state == State::High {
-            // A && B
-            self.pin[2].borrow_mut().state = if self.pin[0].borrow().state == State::High
-                && self.pin[1].borrow().state == State::High
-            {
-                State::High
-            } else {
-                State::Low
-            };
-            // C && D
-            self.pin[5].borrow_mut().state = if self.pin[3].borrow().state == State::High
-                && self.pin[4].borrow().state == State::High
-            {
-                State::High
-            } else {
-                State::Low
-            };
-            // E && F
-            self.pin[10].borrow_mut().state = if self.pin[11].borrow().state == State::High
-                && self.pin[12].borrow().state == State::High
-            {
-                State::High
-            } else {
-                State::Low
-            };
-            // G && H
-            self.pin[7].borrow_mut().state = if self.pin[8].borrow().state == State::High
-                && self.pin[9].borrow().state == State::High
-            {
-                State::High
-            } else {
-                State::Low
-            };
-        } else {
-            // turn off every pin
-            for i in 0..14 {
-                self.pin[i].borrow_mut().state = State::Undefined;
-            }
-        }
+        run_gates(&self.pin, &Self::GATES, get_quirks(Self::TYPE), self.model);
+    }
+
+    fn set_logic_model(&mut self, model: LogicModel) {
+        self.model = model;
     }
 }
 
@@ -288,6 +417,7 @@ impl Chip for GateAnd {
 pub struct Gate3InputAnd {
     uuid: u128,
     pin: [Rc<RefCell<Pin>>; 14],
+    model: LogicModel,
 }
 impl Default for Gate3InputAnd {
     fn default() -> Self {
@@ -313,6 +443,25 @@ impl Gate3InputAnd {
     pub const VCC: u8 = 14;
     pub const GND: u8 = 7;
 
+    /// Gates bundled in the chip
+    pub const GATES: [Gate; 3] = [
+        Gate {
+            kind: GateKind::And,
+            inputs: &[Self::A, Self::B, Self::C],
+            output: Self::A_AND_B_AND_C,
+        },
+        Gate {
+            kind: GateKind::And,
+            inputs: &[Self::D, Self::E, Self::F],
+            output: Self::D_AND_E_AND_F,
+        },
+        Gate {
+            kind: GateKind::And,
+            inputs: &[Self::G, Self::H, Self::I],
+            output: Self::G_AND_H_AND_I,
+        },
+    ];
+
     pub fn new() -> Self {
         let uuid = uuid::Uuid::new_v4().as_u128();
         Self {
@@ -333,6 +482,7 @@ impl Gate3InputAnd {
                 Rc::new(RefCell::new(Pin::new(uuid, 13, PinType::Input))),
                 Rc::new(RefCell::new(Pin::new(uuid, 14, PinType::Input))),
             ],
+            model: LogicModel::Binary,
         }
     }
 }
@@ -360,41 +510,11 @@ impl Chip for Gate3InputAnd {
     }
 
     fn run(&mut self, _: std::time::Duration) {
-        // check alimented
-        if self.pin[6].borrow().state == State::Low && self.pin[13].borrow().state == State::High {
-            // A && B && C
-            self.pin[11].borrow_mut().state = if self.pin[0].borrow().state == State::High
-                && self.pin[1].borrow().state == State::High
-                && self.pin[12].borrow().state == State::High
-            {
-                State::High
-            } else {
-                State::Low
-            };
-            // D && E && F
-            self.pin[5].borrow_mut().state = if self.pin[2].borrow().state == State::High
-                && self.pin[3].borrow().state == State::High
-                && self.pin[4].borrow().state == State::High
-            {
-                State::High
-            } else {
-                State::Low
-            };
-            // G && H && I
-            self.pin[7].borrow_mut().state = if self.pin[10].borrow().state == State::High
-                && self.pin[9].borrow().state == State::High
-                && self.pin[8].borrow().state == State::High
-            {
-                State::High
-            } else {
-                State::Low
-            };
-        } else {
-            // turn off every pin
-            for i in 0..14 {
-                self.pin[i].borrow_mut().state = State::Undefined;
-            }
-        }
+        run_gates(&self.pin, &Self::GATES, get_quirks(Self::TYPE), self.model);
+    }
+
+    fn set_logic_model(&mut self, model: LogicModel) {
+        self.model = model;
     }
 }
 
@@ -416,6 +536,7 @@ impl Chip for Gate3InputAnd {
 pub struct GateNot {
     uuid: u128,
     pin: [Rc<RefCell<Pin>>; 14],
+    model: LogicModel,
 }
 impl Default for GateNot {
     fn default() -> Self {
@@ -441,6 +562,40 @@ impl GateNot {
     pub const VCC: u8 = 14;
     pub const GND: u8 = 7;
 
+    /// Gates bundled in the chip
+    pub const GATES: [Gate; 6] = [
+        Gate {
+            kind: GateKind::Not,
+            inputs: &[Self::A],
+            output: Self::NOT_A,
+        },
+        Gate {
+            kind: GateKind::Not,
+            inputs: &[Self::B],
+            output: Self::NOT_B,
+        },
+        Gate {
+            kind: GateKind::Not,
+            inputs: &[Self::C],
+            output: Self::NOT_C,
+        },
+        Gate {
+            kind: GateKind::Not,
+            inputs: &[Self::D],
+            output: Self::NOT_D,
+        },
+        Gate {
+            kind: GateKind::Not,
+            inputs: &[Self::E],
+            output: Self::NOT_E,
+        },
+        Gate {
+            kind: GateKind::Not,
+            inputs: &[Self::F],
+            output: Self::NOT_F,
+        },
+    ];
+
     pub fn new() -> Self {
         let uuid = uuid::Uuid::new_v4().as_u128();
         Self {
@@ -461,6 +616,7 @@ impl GateNot {
                 Rc::new(RefCell::new(Pin::new(uuid, 13, PinType::Input))),
                 Rc::new(RefCell::new(Pin::new(uuid, 14, PinType::Input))),
             ],
+            model: LogicModel::Binary,
         }
     }
 }
@@ -488,50 +644,11 @@ impl Chip for GateNot {
     }
 
     fn run(&mut self, _: std::time::Duration) {
-        // check alimented
-        if self.pin[6].borrow().state == State::Low && self.pin[13].borrow().state == State::High {
-            // !A
-            self.pin[1].borrow_mut().state = if self.pin[0].borrow().state == State::High {
-                State::Low
-            } else {
-                State::High
-            };
-            // !B
-            self.pin[3].borrow_mut().state = if self.pin[2].borrow().state == State::High {
-                State::Low
-            } else {
-                State::High
-            };
-            // !C
-            self.pin[5].borrow_mut().state = if self.pin[4].borrow().state == State::High {
-                State::Low
-            } else {
-                State::High
-            };
-            // !D
-            self.pin[11].borrow_mut().state = if self.pin[12].borrow().state == State::High {
-                State::Low
-            } else {
-                State::High
-            };
-            // !E
-            self.pin[9].borrow_mut().state = if self.pin[10].borrow().state == State::High {
-                State::Low
-            } else {
-                State::High
-            };
-            // !F
-            self.pin[7].borrow_mut().state = if self.pin[8].borrow().state == State::High {
-                State::Low
-            } else {
-                State::High
-            };
-        } else {
-            // turn off every pin
-            for i in 0..14 {
-                self.pin[i].borrow_mut().state = State::Undefined;
-            }
-        }
+        run_gates(&self.pin, &Self::GATES, get_quirks(Self::TYPE), self.model);
+    }
+
+    fn set_logic_model(&mut self, model: LogicModel) {
+        self.model = model;
     }
 }
 
@@ -553,6 +670,7 @@ impl Chip for GateNot {
 pub struct GateNor {
     uuid: u128,
     pin: [Rc<RefCell<Pin>>; 14],
+    model: LogicModel,
 }
 impl Default for GateNor {
     fn default() -> Self {
@@ -578,6 +696,30 @@ impl GateNor {
     pub const VCC: u8 = 14;
     pub const GND: u8 = 7;
 
+    /// Gates bundled in the chip
+    pub const GATES: [Gate; 4] = [
+        Gate {
+            kind: GateKind::Nor,
+            inputs: &[Self::A, Self::B],
+            output: Self::NOT_A_OR_B,
+        },
+        Gate {
+            kind: GateKind::Nor,
+            inputs: &[Self::C, Self::D],
+            output: Self::NOT_C_OR_D,
+        },
+        Gate {
+            kind: GateKind::Nor,
+            inputs: &[Self::E, Self::F],
+            output: Self::NOT_E_OR_F,
+        },
+        Gate {
+            kind: GateKind::Nor,
+            inputs: &[Self::G, Self::H],
+            output: Self::NOT_G_OR_H,
+        },
+    ];
+
     pub fn new() -> Self {
         let uuid = uuid::Uuid::new_v4().as_u128();
         Self {
@@ -598,6 +740,7 @@ impl GateNor {
                 Rc::new(RefCell::new(Pin::new(uuid, 13, PinType::Output))),
                 Rc::new(RefCell::new(Pin::new(uuid, 14, PinType::Input))),
             ],
+            model: LogicModel::Binary,
         }
     }
 }
@@ -626,46 +769,11 @@ impl Chip for GateNor {
     }
 
     fn run(&mut self, _: std::time::Duration) {
-        // check alimented
-        if self.pin[6].borrow().state == State::Low && self.pin[13].borrow().state == State::High {
-            // A && B
-            self.pin[0].borrow_mut().state = if self.pin[1].borrow().state == State::High
-                || self.pin[2].borrow().state == State::High
-            {
-                State::Low
-            } else {
-                State::High
-            };
-            // C && D
-            self.pin[3].borrow_mut().state = if self.pin[4].borrow().state == State::High
-                || self.pin[5].borrow().state == State::High
-            {
-                State::Low
-            } else {
-                State::High
-            };
-            // E && F
-            self.pin[9].borrow_mut().state = if self.pin[8].borrow().state == State::High
-                || self.pin[7].borrow().state == State::High
-            {
-                State::Low
-            } else {
-                State::High
-            };
-            // G && H
-            self.pin[12].borrow_mut().state = if self.pin[11].borrow().state == State::High
-                || self.pin[10].borrow().state == State::High
-            {
-                State::Low
-            } else {
-                State::High
-            };
-        } else {
-            // turn off every pin
-            for i in 0..14 {
-                self.pin[i].borrow_mut().state = State::Low;
-            }
-        }
+        run_gates(&self.pin, &Self::GATES, get_quirks(Self::TYPE), self.model);
+    }
+
+    fn set_logic_model(&mut self, model: LogicModel) {
+        self.model = model;
     }
 }
 
@@ -687,6 +795,7 @@ impl Chip for GateNor {
 pub struct Gate3InputNor {
     uuid: u128,
     pin: [Rc<RefCell<Pin>>; 14],
+    model: LogicModel,
 }
 impl Default for Gate3InputNor {
     fn default() -> Self {
@@ -712,6 +821,25 @@ impl Gate3InputNor {
     pub const VCC: u8 = 14;
     pub const GND: u8 = 7;
 
+    /// Gates bundled in the chip
+    pub const GATES: [Gate; 3] = [
+        Gate {
+            kind: GateKind::Nor,
+            inputs: &[Self::A, Self::B, Self::C],
+            output: Self::NOT_A_OR_B_OR_C,
+        },
+        Gate {
+            kind: GateKind::Nor,
+            inputs: &[Self::D, Self::E, Self::F],
+            output: Self::NOT_D_OR_E_OR_F,
+        },
+        Gate {
+            kind: GateKind::Nor,
+            inputs: &[Self::G, Self::H, Self::I],
+            output: Self::NOT_G_OR_H_OR_I,
+        },
+    ];
+
     pub fn new() -> Self {
         let uuid = uuid::Uuid::new_v4().as_u128();
         Self {
@@ -732,6 +860,7 @@ impl Gate3InputNor {
                 Rc::new(RefCell::new(Pin::new(uuid, 13, PinType::Input))),
                 Rc::new(RefCell::new(Pin::new(uuid, 14, PinType::Input))),
             ],
+            model: LogicModel::Binary,
         }
     }
 }
@@ -760,41 +889,11 @@ impl Chip for Gate3InputNor {
     }
 
     fn run(&mut self, _: std::time::Duration) {
-        // check alimented
-        if self.pin[6].borrow().state == State::Low && self.pin[13].borrow().state == State::High {
-            // A && B && C
-            self.pin[11].borrow_mut().state = if self.pin[0].borrow().state == State::Low
-                && self.pin[1].borrow().state == State::Low
-                && self.pin[12].borrow().state == State::Low
-            {
-                State::High
-            } else {
-                State::Low
-            };
-            // D && E && F
-            self.pin[5].borrow_mut().state = if self.pin[2].borrow().state == State::Low
-                && self.pin[3].borrow().state == State::Low
-                && self.pin[4].borrow().state == State::Low
-            {
-                State::High
-            } else {
-                State::Low
-            };
-            // G && H && I
-            self.pin[7].borrow_mut().state = if self.pin[10].borrow().state == State::Low
-                && self.pin[9].borrow().state == State::Low
-                && self.pin[8].borrow().state == State::Low
-            {
-                State::High
-            } else {
-                State::Low
-            };
-        } else {
-            // turn off every pin
-            for i in 0..14 {
-                self.pin[i].borrow_mut().state = State::Low;
-            }
-        }
+        run_gates(&self.pin, &Self::GATES, get_quirks(Self::TYPE), self.model);
+    }
+
+    fn set_logic_model(&mut self, model: LogicModel) {
+        self.model = model;
     }
 }
 
@@ -816,6 +915,7 @@ impl Chip for Gate3InputNor {
 pub struct GateNand {
     uuid: u128,
     pin: [Rc<RefCell<Pin>>; 14],
+    model: LogicModel,
 }
 impl Default for GateNand {
     fn default() -> Self {
@@ -841,6 +941,30 @@ impl GateNand {
     pub const VCC: u8 = 14;
     pub const GND: u8 = 7;
 
+    /// Gates bundled in the chip
+    pub const GATES: [Gate; 4] = [
+        Gate {
+            kind: GateKind::Nand,
+            inputs: &[Self::A, Self::B],
+            output: Self::NOT_A_AND_B,
+        },
+        Gate {
+            kind: GateKind::Nand,
+            inputs: &[Self::C, Self::D],
+            output: Self::NOT_C_AND_D,
+        },
+        Gate {
+            kind: GateKind::Nand,
+            inputs: &[Self::E, Self::F],
+            output: Self::NOT_E_AND_F,
+        },
+        Gate {
+            kind: GateKind::Nand,
+            inputs: &[Self::G, Self::H],
+            output: Self::NOT_G_AND_H,
+        },
+    ];
+
     pub fn new() -> Self {
         let uuid = uuid::Uuid::new_v4().as_u128();
         Self {
@@ -861,6 +985,7 @@ impl GateNand {
                 Rc::new(RefCell::new(Pin::new(uuid, 13, PinType::Input))),
                 Rc::new(RefCell::new(Pin::new(uuid, 14, PinType::Input))),
             ],
+            model: LogicModel::Binary,
         }
     }
 }
@@ -888,46 +1013,11 @@ impl Chip for GateNand {
     }
 
     fn run(&mut self, _: std::time::Duration) {
-        // check alimented
-        if self.pin[6].borrow().state == State::Low && self.pin[13].borrow().state == State::High {
-            // A && B
-            self.pin[2].borrow_mut().state = if self.pin[0].borrow().state == State::High
-                && self.pin[1].borrow().state == State::High
-            {
-                State::Low
-            } else {
-                State::High
-            };
-            // C && D
-            self.pin[5].borrow_mut().state = if self.pin[3].borrow().state == State::High
-                && self.pin[4].borrow().state == State::High
-            {
-                State::Low
-            } else {
-                State::High
-            };
-            // E && F
-            self.pin[10].borrow_mut().state = if self.pin[11].borrow().state == State::High
-                && self.pin[12].borrow().state == State::High
-            {
-                State::Low
-            } else {
-                State::High
-            };
-            // G && H
-            self.pin[7].borrow_mut().state = if self.pin[8].borrow().state == State::High
-                && self.pin[9].borrow().state == State::High
-            {
-                State::Low
-            } else {
-                State::High
-            };
-        } else {
-            // turn off every pin
-            for i in 0..14 {
-                self.pin[i].borrow_mut().state = State::Undefined;
-            }
-        }
+        run_gates(&self.pin, &Self::GATES, get_quirks(Self::TYPE), self.model);
+    }
+
+    fn set_logic_model(&mut self, model: LogicModel) {
+        self.model = model;
     }
 }
 
@@ -949,6 +1039,7 @@ impl Chip for GateNand {
 pub struct Gate3InputNand {
     uuid: u128,
     pin: [Rc<RefCell<Pin>>; 14],
+    model: LogicModel,
 }
 impl Default for Gate3InputNand {
     fn default() -> Self {
@@ -974,6 +1065,25 @@ impl Gate3InputNand {
     pub const VCC: u8 = 14;
     pub const GND: u8 = 7;
 
+    /// Gates bundled in the chip
+    pub const GATES: [Gate; 3] = [
+        Gate {
+            kind: GateKind::Nand,
+            inputs: &[Self::A, Self::B, Self::C],
+            output: Self::NOT_A_AND_B_AND_C,
+        },
+        Gate {
+            kind: GateKind::Nand,
+            inputs: &[Self::D, Self::E, Self::F],
+            output: Self::NOT_D_AND_E_AND_F,
+        },
+        Gate {
+            kind: GateKind::Nand,
+            inputs: &[Self::G, Self::H, Self::I],
+            output: Self::NOT_G_AND_H_AND_I,
+        },
+    ];
+
     pub fn new() -> Self {
         let uuid = uuid::Uuid::new_v4().as_u128();
         Self {
@@ -994,6 +1104,7 @@ impl Gate3InputNand {
                 Rc::new(RefCell::new(Pin::new(uuid, 13, PinType::Input))),
                 Rc::new(RefCell::new(Pin::new(uuid, 14, PinType::Input))),
             ],
+            model: LogicModel::Binary,
         }
     }
 }
@@ -1021,40 +1132,10 @@ impl Chip for Gate3InputNand {
     }
 
     fn run(&mut self, _: std::time::Duration) {
-        // check alimented
-        if self.pin[6].borrow().state == State::Low && self.pin[13].borrow().state == State::High {
-            // A && B && C
-            self.pin[11].borrow_mut().state = if self.pin[0].borrow().state == State::High
-                && self.pin[1].borrow().state == State::High
-                && self.pin[12].borrow().state == State::High
-            {
-                State::Low
-            } else {
-                State::High
-            };
-            // D && E && F
-            self.pin[5].borrow_mut().state = if self.pin[2].borrow().state == State::High
-                && self.pin[3].borrow().state == State::High
-                && self.pin[4].borrow().state == State::High
-            {
-                State::Low
-            } else {
-                State::High
-            };
-            // G && H && I
-            self.pin[7].borrow_mut().state = if self.pin[10].borrow().state == State::High
-                && self.pin[9].borrow().state == State::High
-                && self.pin[8].borrow().state == State::High
-            {
-                State::Low
-            } else {
-                State::High
-            };
-        } else {
-            // turn off every pin
-            for i in 0..14 {
-                self.pin[i].borrow_mut().state = State::Undefined;
-            }
-        }
+        run_gates(&self.pin, &Self::GATES, get_quirks(Self::TYPE), self.model);
+    }
+
+    fn set_logic_model(&mut self, model: LogicModel) {
+        self.model = model;
     }
 }
@@ -0,0 +1,356 @@
+//! Programmable combinational logic
+use super::{from_data, to_data, Chip, ChipData, ChipInfo, Pin, PinType};
+use crate::{expression::Expression, Error, Result, State};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// # A combinational chip defined by a truth table
+///
+/// The chip has any number of pins, some of them are inputs and others outputs.
+/// Each row of the table gives the outputs for a combination of the inputs:
+/// the row index has a bit per input (first input as the least significant bit)
+/// and the row value a bit per output (first output as the least significant bit).
+/// Undefined inputs are read as Low.
+///
+/// The table can be built from a boolean expression per output, see the `expression` module for the syntax.
+/// The address decoder of a CPU selecting a RAM on 0xF00-0xFFF and a ROM elsewhere:
+/// ```
+/// use virt_ic::chip::logic::LogicTable;
+///
+/// let decoder = LogicTable::from_expressions(
+///     14,
+///     &[(1, "A8"), (2, "A9"), (3, "A10"), (4, "A11")],
+///     &[(8, "A8 & A9 & A10 & A11"), (9, "!(A8 & A9 & A10 & A11)")],
+/// )
+/// .unwrap()
+/// .with_power(14, 7)
+/// .unwrap();
+/// ```
+/// The whole definition is saved with the chip, so it's loaded back without any specific code.
+#[derive(Debug)]
+pub struct LogicTable {
+    uuid: u128,
+    pin: Vec<Rc<RefCell<Pin>>>,
+    state: LogicTableState,
+}
+impl Default for LogicTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+struct LogicTableState {
+    pin_qty: u8,
+    inputs: Vec<u8>,
+    outputs: Vec<u8>,
+    vcc: Option<u8>,
+    gnd: Option<u8>,
+    table: Vec<u32>,
+}
+
+impl LogicTable {
+    pub const TYPE: &'static str = "virt_ic::LogicTable";
+
+    /// Maximum number of inputs, the table has 2^inputs rows
+    pub const MAX_INPUTS: usize = 16;
+    /// Maximum number of outputs, the bits of a row
+    pub const MAX_OUTPUTS: usize = 32;
+
+    /// Create a chip without any pin, to be defined by loading a saved chip
+    pub fn new() -> Self {
+        Self {
+            uuid: uuid::Uuid::new_v4().as_u128(),
+            pin: vec![],
+            state: LogicTableState::default(),
+        }
+    }
+
+    /// Create a chip of `pin_qty` pins from its truth table
+    pub fn from_table(pin_qty: u8, inputs: &[u8], outputs: &[u8], table: Vec<u32>) -> Result<Self> {
+        let mut chip = Self::new();
+        chip.configure(LogicTableState {
+            pin_qty,
+            inputs: inputs.to_vec(),
+            outputs: outputs.to_vec(),
+            vcc: None,
+            gnd: None,
+            table,
+        })?;
+        Ok(chip)
+    }
+
+    /// Create a chip of `pin_qty` pins from a boolean expression per output
+    /// Inputs are given as (pin, name) and outputs as (pin, expression of the input names).
+    pub fn from_expressions(
+        pin_qty: u8,
+        inputs: &[(u8, &str)],
+        outputs: &[(u8, &str)],
+    ) -> Result<Self> {
+        if inputs.len() > Self::MAX_INPUTS || outputs.len() > Self::MAX_OUTPUTS {
+            return Err(Error::InvalidLogic(format!(
+                "{} inputs and {} outputs, the maximum is {} inputs and {} outputs",
+                inputs.len(),
+                outputs.len(),
+                Self::MAX_INPUTS,
+                Self::MAX_OUTPUTS
+            )));
+        }
+        let names: HashMap<&str, usize> = inputs
+            .iter()
+            .enumerate()
+            .map(|(index, (_, name))| (*name, index))
+            .collect();
+        let mut expressions = vec![];
+        for (pin, source) in outputs {
+            let expression = Expression::parse(source)?;
+            if let Some(unknown) = expression
+                .variables()
+                .into_iter()
+                .find(|name| !names.contains_key(name.as_str()))
+            {
+                return Err(Error::InvalidLogic(format!(
+                    "unknown input `{unknown}` in the expression of pin {pin}"
+                )));
+            }
+            expressions.push(expression);
+        }
+        let mut table = vec![0; 1 << inputs.len()];
+        for (row, bits) in table.iter_mut().enumerate() {
+            let value = |name: &str| names.get(name).map(|index| (row >> index) & 1 == 1);
+            for (bit, expression) in expressions.iter().enumerate() {
+                if expression.eval(&value) == Some(true) {
+                    *bits |= 1 << bit;
+                }
+            }
+        }
+        let input_pins: Vec<u8> = inputs.iter().map(|(pin, _)| *pin).collect();
+        let output_pins: Vec<u8> = outputs.iter().map(|(pin, _)| *pin).collect();
+        Self::from_table(pin_qty, &input_pins, &output_pins, table)
+    }
+
+    /// Power the chip with the `vcc` and `gnd` pins, the outputs are undefined while it's not powered
+    pub fn with_power(mut self, vcc: u8, gnd: u8) -> Result<Self> {
+        let mut state = self.state.clone();
+        state.vcc = Some(vcc);
+        state.gnd = Some(gnd);
+        self.configure(state)?;
+        Ok(self)
+    }
+
+    pub fn get_inputs(&self) -> &[u8] {
+        &self.state.inputs
+    }
+
+    pub fn get_outputs(&self) -> &[u8] {
+        &self.state.outputs
+    }
+
+    pub fn get_table(&self) -> &[u32] {
+        &self.state.table
+    }
+
+    /// Check the definition and create the pins
+    /// Pins are kept when their number doesn't change, so that they stay linked to their traces.
+    fn configure(&mut self, state: LogicTableState) -> Result<()> {
+        if state.inputs.len() > Self::MAX_INPUTS || state.outputs.len() > Self::MAX_OUTPUTS {
+            return Err(Error::InvalidLogic(format!(
+                "{} inputs and {} outputs, the maximum is {} inputs and {} outputs",
+                state.inputs.len(),
+                state.outputs.len(),
+                Self::MAX_INPUTS,
+                Self::MAX_OUTPUTS
+            )));
+        }
+        if state.table.len() != 1 << state.inputs.len() {
+            return Err(Error::InvalidLogic(format!(
+                "the table of {} inputs must have {} rows, found {}",
+                state.inputs.len(),
+                1 << state.inputs.len(),
+                state.table.len()
+            )));
+        }
+        let mut types = vec![PinType::Undefined; state.pin_qty as usize];
+        let used = state
+            .inputs
+            .iter()
+            .map(|pin| (pin, PinType::Input))
+            .chain(state.outputs.iter().map(|pin| (pin, PinType::Output)))
+            .chain(state.vcc.iter().map(|pin| (pin, PinType::Input)))
+            .chain(state.gnd.iter().map(|pin| (pin, PinType::Input)));
+        for (pin, pin_type) in used {
+            match types.get_mut((*pin as usize).wrapping_sub(1)) {
+                Some(slot) if *slot == PinType::Undefined => *slot = pin_type,
+                Some(_) => {
+                    return Err(Error::InvalidLogic(format!("pin {pin} is used twice")));
+                }
+                None => {
+                    return Err(Error::PinOutOfBounds {
+                        pin: *pin,
+                        pin_qty: state.pin_qty,
+                    });
+                }
+            }
+        }
+        if self.pin.len() != types.len() {
+            self.pin = (1..=state.pin_qty)
+                .map(|number| {
+                    Rc::new(RefCell::new(Pin::new(
+                        self.uuid,
+                        number,
+                        PinType::Undefined,
+                    )))
+                })
+                .collect();
+        }
+        for (pin, pin_type) in self.pin.iter().zip(types) {
+            pin.borrow_mut().pin_type = pin_type;
+        }
+        self.state = state;
+        Ok(())
+    }
+
+    fn is_high(&self, pin: u8) -> bool {
+        self.pin[pin as usize - 1].borrow().state == State::High
+    }
+}
+
+impl std::fmt::Display for LogicTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for pin in &self.state.inputs {
+            write!(f, "{pin:>3}")?;
+        }
+        write!(f, " |")?;
+        for pin in &self.state.outputs {
+            write!(f, "{pin:>3}")?;
+        }
+        for (row, bits) in self.state.table.iter().enumerate() {
+            writeln!(f)?;
+            for index in 0..self.state.inputs.len() {
+                write!(f, "{:>3}", (row >> index) & 1)?;
+            }
+            write!(f, " |")?;
+            for index in 0..self.state.outputs.len() {
+                write!(f, "{:>3}", (bits >> index) & 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Chip for LogicTable {
+    fn get_uuid(&self) -> u128 {
+        self.uuid
+    }
+    fn get_type(&self) -> &str {
+        Self::TYPE
+    }
+    fn get_pin_qty(&self) -> u8 {
+        self.state.pin_qty
+    }
+
+    fn _get_pin(&mut self, pin: u8) -> Rc<RefCell<Pin>> {
+        self.pin[pin as usize - 1].clone()
+    }
+
+    fn get_info(&self) -> ChipInfo {
+        ChipInfo {
+            name: "Logic Table",
+            description:
+                "A combinational chip whose outputs are defined by a truth table of its inputs.",
+            data: self.to_string(),
+        }
+    }
+
+    fn run(&mut self, _: std::time::Duration) {
+        let powered = match (self.state.vcc, self.state.gnd) {
+            (Some(vcc), Some(gnd)) => {
+                self.is_high(vcc) && self.pin[gnd as usize - 1].borrow().state == State::Low
+            }
+            _ => true,
+        };
+        if powered {
+            let mut row = 0;
+            for (index, pin) in self.state.inputs.iter().enumerate() {
+                if self.is_high(*pin) {
+                    row |= 1 << index;
+                }
+            }
+            // a chip that isn't defined yet has an empty table and drives nothing
+            if let Some(bits) = self.state.table.get(row) {
+                for (index, pin) in self.state.outputs.iter().enumerate() {
+                    self.pin[*pin as usize - 1].borrow_mut().state =
+                        State::from((bits >> index) & 1 == 1);
+                }
+            }
+        } else {
+            for pin in &self.state.outputs {
+                self.pin[*pin as usize - 1].borrow_mut().state = State::Undefined;
+            }
+        }
+    }
+
+    fn save_data(&self) -> Result<ChipData> {
+        to_data(&self.state)
+    }
+    fn load_data(&mut self, chip_data: &ChipData) -> Result<()> {
+        let state: LogicTableState = from_data(Self::TYPE, chip_data)?;
+        self.configure(state)
+    }
+    fn snapshot(&self) -> Box<dyn Any> {
+        // the definition doesn't change while running, there's no state to capture
+        Box::new(())
+    }
+    fn restore(&mut self, _: &dyn Any) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn too_many_outputs_is_an_error() {
+        let outputs: Vec<(u8, &str)> = (2..=34).map(|pin| (pin, "A")).collect();
+        assert!(matches!(
+            LogicTable::from_expressions(34, &[(1, "A")], &outputs),
+            Err(Error::InvalidLogic(_))
+        ));
+    }
+
+    #[test]
+    fn expressions_fill_the_table() {
+        let chip =
+            LogicTable::from_expressions(4, &[(1, "A"), (2, "B")], &[(3, "A & !B"), (4, "A | B")])
+                .unwrap();
+        assert_eq!(chip.get_table(), &[0b00, 0b11, 0b10, 0b10]);
+    }
+
+    #[test]
+    fn malformed_expressions_are_errors() {
+        assert!(matches!(
+            LogicTable::from_expressions(3, &[(1, "A"), (2, "B")], &[(3, "A & (B")]),
+            Err(Error::Expression(_))
+        ));
+        assert!(matches!(
+            LogicTable::from_expressions(3, &[(1, "A"), (2, "B")], &[(3, "A & C")]),
+            Err(Error::InvalidLogic(_))
+        ));
+    }
+
+    #[test]
+    fn undefined_chips_run_without_driving() {
+        let mut board = crate::Board::from_netlist(
+            "part U1 virt_ic::LogicTable",
+            &crate::chip::ChipRegistry::new(),
+        )
+        .unwrap();
+        board.run(std::time::Duration::from_millis(1));
+        let socket = board.get_socket_by_name("U1").unwrap();
+        assert_eq!(socket.borrow().get_type(), LogicTable::TYPE);
+    }
+}
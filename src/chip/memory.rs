@@ -1,7 +1,9 @@
 //! Readable and/or Writable Memory Chips
-use super::{Chip, ChipInfo, Pin, PinType};
-use crate::State;
+use super::{from_data, legacy_data, to_data, Chip, ChipData, ChipInfo, Pin, PinType};
+use crate::{Error, Result, State};
 use rand::random;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -113,6 +115,15 @@ impl Ram256B {
         }
     }
 
+    /// Get the content of the RAM
+    pub fn get_ram(&self) -> &[u8; 256] {
+        &self.ram
+    }
+
+    pub fn get_ram_mut(&mut self) -> &mut [u8; 256] {
+        &mut self.ram
+    }
+
     fn get_address(&self) -> u8 {
         let mut addr: u8 = 0;
         for i in 3..10 {
@@ -133,6 +144,12 @@ impl Ram256B {
         addr
     }
 }
+#[derive(Serialize, Deserialize)]
+struct RamState {
+    ram: Vec<u8>,
+    powered: bool,
+}
+
 impl Chip for Ram256B {
     fn get_uuid(&self) -> u128 {
         self.uuid
@@ -215,16 +232,54 @@ The data is not kept if the chip is no longer powered.",
         }
     }
 
-    fn save_data(&self) -> Vec<String> {
-        vec![
-            ron::to_string(&self.ram.to_vec()).unwrap(),
-            String::from(if self.powered { "ON" } else { "OFF" }),
-        ]
+    fn data_version(&self) -> u32 {
+        1
+    }
+    fn migrate_data(&self, from_version: u32, chip_data: ChipData) -> Option<ChipData> {
+        match from_version {
+            0 => {
+                let legacy = legacy_data(chip_data)?;
+                to_data(&RamState {
+                    ram: ron::from_str(legacy.first()?).ok()?,
+                    powered: legacy.get(1)? == "ON",
+                })
+                .ok()
+            }
+            _ => None,
+        }
+    }
+    fn save_data(&self) -> Result<ChipData> {
+        to_data(&RamState {
+            ram: self.ram.to_vec(),
+            powered: self.powered,
+        })
+    }
+    fn load_data(&mut self, chip_data: &ChipData) -> Result<()> {
+        let state: RamState = from_data(Self::TYPE, chip_data)?;
+        if state.ram.len() != self.ram.len() {
+            return Err(Error::invalid_chip_data(
+                Self::TYPE,
+                format!(
+                    "expected {} bytes, found {}",
+                    self.ram.len(),
+                    state.ram.len()
+                ),
+            ));
+        }
+        self.ram.copy_from_slice(&state.ram);
+        self.powered = state.powered;
+        Ok(())
     }
-    fn load_data(&mut self, chip_data: &[String]) {
-        let data: Vec<u8> = ron::from_str(&chip_data[0]).unwrap();
-        self.ram.copy_from_slice(&data[..data.len()]);
-        self.powered = chip_data[1] == "ON";
+    fn snapshot(&self) -> Box<dyn Any> {
+        Box::new((self.ram, self.powered))
+    }
+    fn restore(&mut self, snapshot: &dyn Any) -> Result<()> {
+        let (ram, powered) = snapshot
+            .downcast_ref::<([u8; 256], bool)>()
+            .ok_or_else(|| Error::invalid_snapshot(Self::TYPE))?;
+        self.ram = *ram;
+        self.powered = *powered;
+        Ok(())
     }
 }
 
@@ -342,6 +397,11 @@ impl Rom256B {
         self.rom.clone_from_slice(&data);
     }
 
+    /// Get the content of the ROM
+    pub fn get_rom(&self) -> &[u8; 256] {
+        &self.rom
+    }
+
     fn get_address(&self) -> u8 {
         let mut addr: u8 = 0;
         for i in 3..10 {
@@ -353,6 +413,11 @@ impl Rom256B {
         addr
     }
 }
+#[derive(Serialize, Deserialize)]
+struct RomState {
+    rom: Vec<u8>,
+}
+
 impl Chip for Rom256B {
     fn get_uuid(&self) -> u128 {
         self.uuid
@@ -417,11 +482,48 @@ The data is kept if the chip is no longer powered.",
         }
     }
 
-    fn save_data(&self) -> Vec<String> {
-        vec![ron::to_string(&self.rom.to_vec()).unwrap()]
+    fn data_version(&self) -> u32 {
+        1
+    }
+    fn migrate_data(&self, from_version: u32, chip_data: ChipData) -> Option<ChipData> {
+        match from_version {
+            0 => {
+                let legacy = legacy_data(chip_data)?;
+                to_data(&RomState {
+                    rom: ron::from_str(legacy.first()?).ok()?,
+                })
+                .ok()
+            }
+            _ => None,
+        }
+    }
+    fn save_data(&self) -> Result<ChipData> {
+        to_data(&RomState {
+            rom: self.rom.to_vec(),
+        })
+    }
+    fn load_data(&mut self, chip_data: &ChipData) -> Result<()> {
+        let state: RomState = from_data(Self::TYPE, chip_data)?;
+        if state.rom.len() != self.rom.len() {
+            return Err(Error::invalid_chip_data(
+                Self::TYPE,
+                format!(
+                    "expected {} bytes, found {}",
+                    self.rom.len(),
+                    state.rom.len()
+                ),
+            ));
+        }
+        self.rom.copy_from_slice(&state.rom);
+        Ok(())
+    }
+    fn snapshot(&self) -> Box<dyn Any> {
+        Box::new(self.rom)
     }
-    fn load_data(&mut self, chip_data: &[String]) {
-        let data: Vec<u8> = ron::from_str(&chip_data[0]).unwrap();
-        self.rom.copy_from_slice(&data[..data.len()]);
+    fn restore(&mut self, snapshot: &dyn Any) -> Result<()> {
+        self.rom = *snapshot
+            .downcast_ref::<[u8; 256]>()
+            .ok_or_else(|| Error::invalid_snapshot(Self::TYPE))?;
+        Ok(())
     }
 }
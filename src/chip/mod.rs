@@ -1,13 +1,20 @@
 //! Chip trait, Pins and premade Chips
-use super::State;
+use super::{LogicModel, State, StdLogic};
+pub mod analog;
 pub mod buttons;
 pub mod clocks;
 pub mod cpu;
 pub mod gates;
 pub mod generators;
+pub mod logic;
 pub mod memory;
+mod registry;
+pub mod subboard;
 use super::save::SavedChip;
+use super::{Error, Result};
+pub use registry::{ChipMetadata, ChipRegistry};
 use serde::{Deserialize, Serialize};
+use std::any::Any;
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -27,7 +34,23 @@ pub struct Pin {
     pub number: u8,
     pub pin_type: PinType,
     pub state: State,
+    /// Level of the pin with the `StdLogic` model, it isn't saved
+    /// It's only used while it matches `state`, see `get_level`.
+    /// A pin that was never driven is high impedance, like an Undefined state.
+    #[serde(skip, default = "high_impedance")]
+    pub level: StdLogic,
+    /// Analog voltage of the pin, it isn't saved
+    /// Output pins drive it while their `state` is Undefined, the other pins get the voltage of their trace.
+    #[serde(skip)]
+    pub voltage: Option<f64>,
+    /// The pin reads or drives a voltage, traces only pass voltages along when one of their pins does
+    #[serde(skip)]
+    pub analog: bool,
 }
+fn high_impedance() -> StdLogic {
+    StdLogic::HighImpedance
+}
+
 impl Pin {
     pub fn new(parent_uuid: u128, number: u8, pin_type: PinType) -> Self {
         Self {
@@ -35,8 +58,27 @@ impl Pin {
             number,
             pin_type,
             state: State::Undefined,
+            level: StdLogic::HighImpedance,
+            voltage: None,
+            analog: false,
         }
     }
+
+    /// Get the `StdLogic` level of the pin
+    /// If a chip changed its `state` since the level was set, the level follows the state.
+    pub fn get_level(&self) -> StdLogic {
+        if State::from(self.level) == self.state {
+            self.level
+        } else {
+            StdLogic::from(self.state.clone())
+        }
+    }
+
+    /// Set the `StdLogic` level of the pin, and its state accordingly
+    pub fn set_level(&mut self, level: StdLogic) {
+        self.level = level;
+        self.state = State::from(level);
+    }
 }
 
 pub struct ChipInfo {
@@ -45,8 +87,24 @@ pub struct ChipInfo {
     pub data: String,
 }
 
+/// Access a value as `Any`, to downcast a chip to its struct
+/// It's implemented for every `'static` type, chips don't have to implement it.
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
 /// Chip : a trait that represents chips on board
-pub trait Chip {
+pub trait Chip: AsAny {
     /// Give a unique id to maintain continuity when saving.  
     /// This uuid must not maintain any information other that identity.  
     /// When saving, this value will be used to link the traced pins to their respective chip.
@@ -62,11 +120,12 @@ pub trait Chip {
     /// There is no way that you don't provide a pin since you have said in `pin_qty` how many pins your chip have
     fn _get_pin(&mut self, pin: u8) -> Rc<RefCell<Pin>>;
     /// Get a pin of the chip
-    fn get_pin(&mut self, pin: u8) -> Result<Rc<RefCell<Pin>>, &str> {
-        if pin > 0 && pin <= self.get_pin_qty() {
+    fn get_pin(&mut self, pin: u8) -> Result<Rc<RefCell<Pin>>> {
+        let pin_qty = self.get_pin_qty();
+        if pin > 0 && pin <= pin_qty {
             Ok(self._get_pin(pin))
         } else {
-            Err("Pin out of bounds")
+            Err(Error::PinOutOfBounds { pin, pin_qty })
         }
     }
     /// Get the state of the specified Pin
@@ -82,25 +141,77 @@ pub trait Chip {
     }
     /// Get chip generic informations and data
     fn get_info(&self) -> ChipInfo;
-    /// Save the chip to a `SavedChip` struct
-    fn save(&self) -> SavedChip {
-        SavedChip {
+    /// Set the logic model the chip computes its outputs with.  
+    /// Chips that only know `State` can ignore it, their pins follow their states with any model.
+    fn set_logic_model(&mut self, _model: LogicModel) {}
+    /// Save the chip to a `SavedChip` struct, fails if its data can't be serialized
+    fn save(&self) -> Result<SavedChip> {
+        Ok(SavedChip {
             uuid: self.get_uuid(),
             chip_type: String::from(self.get_type()),
-            chip_data: self.save_data(),
-        }
+            data_version: self.data_version(),
+            chip_data: self.save_data()?,
+        })
+    }
+    /// Version of the layout of the data returned by `save_data`.  
+    /// Increase it every time this layout changes, and convert the older layouts in `migrate_data`.
+    fn data_version(&self) -> u32 {
+        0
     }
-    /// Create a Vec of String that must contain every information you need to restore your chip to a certain state.  
-    /// This will be saved in the resulting file.
-    fn save_data(&self) -> Vec<String> {
-        vec![]
+    /// Convert the data saved with another `data_version` to the current layout of `save_data`.  
+    /// Returns `None` if the data can't be converted, loading the chip then fails.  
+    /// Data saved before chips had a structured state is an array of strings with version 0, see `legacy_data`.
+    fn migrate_data(&self, _from_version: u32, _chip_data: ChipData) -> Option<ChipData> {
+        None
+    }
+    /// Create a structured value that must contain every information you need to restore your chip to a certain state.  
+    /// This will be saved in the resulting file, use `to_data` to build it from a serializable struct.
+    fn save_data(&self) -> Result<ChipData> {
+        Ok(ChipData::Null)
     }
     /// Restore the chip from a `SavedChip` struct
-    fn load(&mut self, saved_chip: &SavedChip) {
-        self.load_data(&saved_chip.chip_data);
+    fn load(&mut self, saved_chip: &SavedChip) -> Result<()> {
+        if saved_chip.data_version == self.data_version() {
+            self.load_data(&saved_chip.chip_data)
+        } else if let Some(chip_data) =
+            self.migrate_data(saved_chip.data_version, saved_chip.chip_data.clone())
+        {
+            self.load_data(&chip_data)
+        } else {
+            Err(Error::invalid_chip_data(
+                self.get_type(),
+                format!(
+                    "can't migrate data version {} to version {}",
+                    saved_chip.data_version,
+                    self.data_version()
+                ),
+            ))
+        }
+    }
+    /// Using the value you provided in `save_data` , you must restore the state of your chip.  
+    /// Missing or malformed data must be reported as an `Error::InvalidChipData`, see `from_data`.
+    fn load_data(&mut self, _chip_data: &ChipData) -> Result<()> {
+        Ok(())
+    }
+    /// Capture the internal state of the chip in memory, to be given back to `restore`.  
+    /// By default it goes through `save_data`, chips with a state should copy it directly instead.
+    /// A failure of `save_data` is reported when the snapshot is restored.
+    fn snapshot(&self) -> Box<dyn Any> {
+        Box::new(self.save_data())
+    }
+    /// Put back an internal state captured by `snapshot`
+    fn restore(&mut self, snapshot: &dyn Any) -> Result<()> {
+        let chip_data = snapshot
+            .downcast_ref::<Result<ChipData>>()
+            .ok_or_else(|| Error::invalid_snapshot(self.get_type()))?;
+        match chip_data {
+            Ok(chip_data) => self.load_data(chip_data),
+            Err(err) => Err(Error::InvalidSnapshot(format!(
+                "the state of `{}` couldn't be captured: {err}",
+                self.get_type()
+            ))),
+        }
     }
-    /// Using the array of String you provided in `save_data` , you must restore the state of your chip.
-    fn load_data(&mut self, _chip_data: &[String]) {}
 }
 impl std::fmt::Debug for dyn Chip {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
@@ -112,31 +223,134 @@ impl std::fmt::Debug for dyn Chip {
     }
 }
 
+/// The structured state of a chip, saved inside the board's save
+pub type ChipData = serde_json::Value;
+
+/// Convert the state of a chip to `ChipData`
+pub fn to_data<T: Serialize>(state: &T) -> Result<ChipData> {
+    Ok(serde_json::to_value(state)?)
+}
+
+/// Read the state of a chip from the `ChipData` saved by `save_data`
+pub fn from_data<T: serde::de::DeserializeOwned>(
+    chip_type: &str,
+    chip_data: &ChipData,
+) -> Result<T> {
+    T::deserialize(chip_data).map_err(|err| Error::invalid_chip_data(chip_type, err.to_string()))
+}
+
+/// Read the data saved before chips had a structured state, as the strings returned by the former `save_data`
+pub fn legacy_data(chip_data: ChipData) -> Option<Vec<String>> {
+    serde_json::from_value(chip_data).ok()
+}
+
 /// Factory function for chips that are built-in for the `virt_ic` crate.
 ///
-/// You can provide your own factory function to the `Board::Load` to build your custom chips.
+/// It's kept for compatibility, `ChipRegistry::new()` builds the same chips and can be extended with your own.
 /// ```
-/// pub fn my_custom_factory(chip_name: &str) -> Option<Box<dyn Chip>> {
-///     if let Some(chip) = virt_ic::chip::virt_ic_chip_factory(chip_name) {
-///         return chip;
-///     } else {
-///         // build your chips here
-///         // with a match chip_name for example
-///     }
-/// }
+/// #![allow(deprecated)]
+/// let chip = virt_ic::chip::virt_ic_chip_factory("virt_ic::GateAnd");
+/// assert!(chip.is_some());
 /// ```
+#[deprecated(note = "use `ChipRegistry` instead")]
 pub fn virt_ic_chip_factory(chip_name: &str) -> Option<Box<dyn Chip>> {
-    match chip_name {
-        "virt_ic::Button" => Some(Box::new(buttons::Button::new())),
-        "virt_ic::Clock100Hz" => Some(Box::new(clocks::Clock100Hz::new())),
-        "virt_ic::Clock1kHz" => Some(Box::new(clocks::Clock1kHz::new())),
-        "virt_ic::SimpleCPU" => Some(Box::new(cpu::SimpleCPU::new())),
-        "virt_ic::GateOr" => Some(Box::new(gates::GateOr::new())),
-        "virt_ic::GateAnd" => Some(Box::new(gates::GateAnd::new())),
-        "virt_ic::GateNot" => Some(Box::new(gates::GateNot::new())),
-        "virt_ic::Generator" => Some(Box::new(generators::Generator::new())),
-        "virt_ic::Ram256B" => Some(Box::new(memory::Ram256B::new())),
-        "virt_ic::Rom256B" => Some(Box::new(memory::Rom256B::new())),
-        _ => None,
+    ChipRegistry::new().create(chip_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board, SaveFormat};
+    use std::collections::HashMap;
+
+    /// A chip whose state can't be serialized, JSON maps need string keys
+    struct Unserializable {
+        pin: Rc<RefCell<Pin>>,
+    }
+
+    impl Chip for Unserializable {
+        fn get_uuid(&self) -> u128 {
+            1
+        }
+        fn get_type(&self) -> &str {
+            "test::Unserializable"
+        }
+        fn run(&mut self, _: std::time::Duration) {}
+        fn get_pin_qty(&self) -> u8 {
+            1
+        }
+        fn _get_pin(&mut self, _: u8) -> Rc<RefCell<Pin>> {
+            self.pin.clone()
+        }
+        fn get_info(&self) -> ChipInfo {
+            ChipInfo {
+                name: "Unserializable",
+                description: "",
+                data: String::new(),
+            }
+        }
+        fn save_data(&self) -> Result<ChipData> {
+            let mut state = HashMap::new();
+            state.insert((1, 2), 3);
+            to_data(&state)
+        }
+    }
+
+    #[test]
+    fn serialization_failures_are_reported() {
+        let mut board = Board::new();
+        board.new_socket_with(Box::new(Unserializable {
+            pin: Rc::new(RefCell::new(Pin::new(1, 1, PinType::Input))),
+        }));
+        assert!(matches!(
+            board.save_to_string(SaveFormat::Json),
+            Err(Error::Json(_))
+        ));
+        let snapshot = board.snapshot();
+        assert!(matches!(
+            board.restore(&snapshot),
+            Err(Error::InvalidSnapshot(_))
+        ));
+    }
+
+    #[test]
+    fn undriven_outputs_are_high_impedance() {
+        let undriven = Rc::new(RefCell::new(Pin::new(1, 1, PinType::Output)));
+        let driver = Rc::new(RefCell::new(Pin::new(2, 1, PinType::Output)));
+        driver.borrow_mut().set_level(StdLogic::High);
+        let mut trace = crate::Trace::new();
+        trace.set_logic_model(LogicModel::StdLogic);
+        trace.connect(undriven.clone());
+        trace.connect(driver);
+        assert_eq!(undriven.borrow().get_level(), StdLogic::HighImpedance);
+        assert_eq!(trace.get_level(), StdLogic::High);
+        assert_eq!(trace.get_state(), State::High);
+
+        let loaded: Pin =
+            serde_json::from_value(serde_json::to_value(&*undriven.borrow()).unwrap()).unwrap();
+        assert_eq!(loaded.get_level(), StdLogic::HighImpedance);
+    }
+
+    #[test]
+    fn failed_restores_leave_the_board_unchanged() {
+        let mut board = Board::new();
+        let ram = board.new_socket_with(Box::new(memory::Ram256B::new()));
+        board.new_socket_with(Box::new(Unserializable {
+            pin: Rc::new(RefCell::new(Pin::new(1, 1, PinType::Input))),
+        }));
+        let byte = |ram: &Rc<RefCell<crate::Socket>>| {
+            ram.borrow_mut()
+                .get_chip_as::<memory::Ram256B>()
+                .unwrap()
+                .get_ram()[0]
+        };
+        let before = byte(&ram);
+        let snapshot = board.snapshot();
+        ram.borrow_mut()
+            .get_chip_as::<memory::Ram256B>()
+            .unwrap()
+            .get_ram_mut()[0] = !before;
+        assert!(board.restore(&snapshot).is_err());
+        assert_eq!(byte(&ram), !before);
     }
 }
@@ -0,0 +1,130 @@
+//! Registry of the chip types that can be built by name
+use super::{analog, buttons, clocks, cpu, gates, generators, logic, memory, Chip};
+use std::collections::BTreeMap;
+
+/// Informations about a registered chip type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChipMetadata {
+    /// The value returned by `Chip::get_type`
+    pub chip_type: String,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub pin_qty: u8,
+}
+
+struct RegisteredChip {
+    metadata: ChipMetadata,
+    constructor: Box<dyn Fn() -> Box<dyn Chip>>,
+}
+
+/// Builds chips from their type, when loading a board or parsing a netlist
+///
+/// `ChipRegistry::new()` knows every built-in chip, register your own chips on top of them:
+/// ```
+/// use virt_ic::chip::{gates::GateAnd, ChipRegistry};
+///
+/// let mut registry = ChipRegistry::new();
+/// // register a chip type with its `Default` implementation
+/// registry.register::<GateAnd>();
+/// // or with any constructor
+/// registry.register_with(|| Box::new(GateAnd::new()));
+///
+/// let chip = registry.create(GateAnd::TYPE).unwrap();
+/// for metadata in registry.list() {
+///     println!("{}: {}", metadata.chip_type, metadata.description);
+/// }
+/// ```
+pub struct ChipRegistry {
+    chips: BTreeMap<String, RegisteredChip>,
+}
+
+impl ChipRegistry {
+    /// Create a registry with every built-in chip
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register::<analog::ResistorDivider>();
+        registry.register::<analog::Potentiometer>();
+        registry.register::<analog::Capacitor>();
+        registry.register::<analog::SchmittTrigger>();
+        registry.register::<analog::Comparator>();
+        registry.register::<buttons::Button>();
+        registry.register::<clocks::Clock100Hz>();
+        registry.register::<clocks::Clock1kHz>();
+        registry.register::<cpu::SimpleCPU>();
+        registry.register::<gates::GateOr>();
+        registry.register::<gates::GateAnd>();
+        registry.register::<gates::Gate3InputAnd>();
+        registry.register::<gates::GateNot>();
+        registry.register::<gates::GateNor>();
+        registry.register::<gates::Gate3InputNor>();
+        registry.register::<gates::GateNand>();
+        registry.register::<gates::Gate3InputNand>();
+        registry.register::<generators::Generator>();
+        registry.register::<logic::LogicTable>();
+        registry.register::<memory::Ram256B>();
+        registry.register::<memory::Rom256B>();
+        registry
+    }
+
+    /// Create a registry without any chip
+    pub fn empty() -> Self {
+        Self {
+            chips: BTreeMap::new(),
+        }
+    }
+
+    /// Register a chip type, built with its `Default` implementation
+    pub fn register<C: Chip + Default + 'static>(&mut self) {
+        self.register_with(|| Box::new(C::default()));
+    }
+
+    /// Register the chip type built by `constructor`
+    /// The type and metadata are read from a chip built once. Registering a type again replaces it.
+    pub fn register_with<F: Fn() -> Box<dyn Chip> + 'static>(&mut self, constructor: F) {
+        let chip = constructor();
+        let info = chip.get_info();
+        let metadata = ChipMetadata {
+            chip_type: String::from(chip.get_type()),
+            name: info.name,
+            description: info.description,
+            pin_qty: chip.get_pin_qty(),
+        };
+        self.chips.insert(
+            metadata.chip_type.clone(),
+            RegisteredChip {
+                metadata,
+                constructor: Box::new(constructor),
+            },
+        );
+    }
+
+    /// Build a new chip of the given type, if it's registered
+    pub fn create(&self, chip_type: &str) -> Option<Box<dyn Chip>> {
+        self.chips.get(chip_type).map(|chip| (chip.constructor)())
+    }
+
+    pub fn contains(&self, chip_type: &str) -> bool {
+        self.chips.contains_key(chip_type)
+    }
+
+    pub fn get_metadata(&self, chip_type: &str) -> Option<&ChipMetadata> {
+        self.chips.get(chip_type).map(|chip| &chip.metadata)
+    }
+
+    /// List the registered chip types, sorted by type
+    pub fn list(&self) -> Vec<&ChipMetadata> {
+        self.chips.values().map(|chip| &chip.metadata).collect()
+    }
+}
+
+impl Default for ChipRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ChipRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.chips.keys()).finish()
+    }
+}
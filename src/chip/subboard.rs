@@ -0,0 +1,281 @@
+//! Boards packaged as chips
+use super::{from_data, to_data, Chip, ChipData, ChipInfo, Pin, PinType};
+use crate::{save::SavedBoard, Board, BoardSnapshot, Error, LogicModel, Result, State, Trace};
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+struct Port {
+    /// The pin of the chip
+    pin: Rc<RefCell<Pin>>,
+    trace: Rc<RefCell<Trace>>,
+    /// The pin driving the trace for an input, `None` for an output
+    driver: Option<Rc<RefCell<Pin>>>,
+}
+
+/// # A board packaged as a chip
+///
+/// Traces of the inner board are exposed as the pins of the chip, numbered in the order they were exposed.
+/// An input pin drives its trace with the state it receives, an output pin takes the state driven on its trace.
+/// ```
+/// use virt_ic::chip::{gates::GateNot, generators::Generator, subboard::SubBoard, Chip, ChipRegistry};
+/// use virt_ic::Board;
+///
+/// fn inverter() -> SubBoard {
+///     let board = Board::from_netlist(
+///         "part PWR virt_ic::Generator
+///          part U1 virt_ic::GateNot
+///          net VCC PWR.1 U1.14
+///          net GND PWR.2 U1.7
+///          net A U1.1
+///          net Y U1.2",
+///         &ChipRegistry::new(),
+///     )
+///     .unwrap();
+///     let mut chip = SubBoard::new("my_crate::Inverter", board);
+///     chip.expose_input("A").unwrap();
+///     chip.expose_output("Y").unwrap();
+///     chip
+/// }
+///
+/// // register the sub-board to load boards using it
+/// let mut registry = ChipRegistry::new();
+/// registry.register_with(|| Box::new(inverter()));
+/// ```
+/// Saves hold the state of the chips and pins of the inner board, not the board itself:
+/// `load_data` has no registry to create its chips with, so loading a board using a sub-board
+/// needs a constructor registered for its type that builds the same inner board, nested sub-boards included.
+/// Loading fails with an `Error::InvalidChipData` if the saved sockets don't match the built ones.
+pub struct SubBoard {
+    uuid: u128,
+    chip_type: String,
+    board: Board,
+    ports: Vec<Port>,
+}
+
+struct SubBoardSnapshot {
+    board: BoardSnapshot,
+    pins: Vec<(PinType, State)>,
+}
+
+impl SubBoard {
+    /// Package a board as a chip of type `chip_type`
+    pub fn new(chip_type: &str, board: Board) -> Self {
+        Self {
+            uuid: uuid::Uuid::new_v4().as_u128(),
+            chip_type: String::from(chip_type),
+            board,
+            ports: vec![],
+        }
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_board_mut(&mut self) -> &mut Board {
+        &mut self.board
+    }
+
+    /// Expose the trace named `trace_name` as an input pin, and return the number of the pin
+    pub fn expose_input(&mut self, trace_name: &str) -> Result<u8> {
+        self.expose(trace_name, true)
+    }
+
+    /// Expose the trace named `trace_name` as an output pin, and return the number of the pin
+    pub fn expose_output(&mut self, trace_name: &str) -> Result<u8> {
+        self.expose(trace_name, false)
+    }
+
+    fn expose(&mut self, trace_name: &str, input: bool) -> Result<u8> {
+        let trace = self
+            .board
+            .get_trace_by_name(trace_name)
+            .ok_or_else(|| Error::UnknownTrace(String::from(trace_name)))?;
+        if self.ports.len() >= u8::MAX as usize {
+            return Err(Error::PinOutOfBounds {
+                pin: u8::MAX,
+                pin_qty: u8::MAX,
+            });
+        }
+        let number = self.ports.len() as u8 + 1;
+        let (pin_type, driver) = if input {
+            // the driver isn't plugged on the inner board, it's dropped from netlists and saves
+            let driver = Rc::new(RefCell::new(Pin::new(self.uuid, number, PinType::Output)));
+            trace.borrow_mut().connect(driver.clone());
+            (PinType::Input, Some(driver))
+        } else {
+            (PinType::Output, None)
+        };
+        self.ports.push(Port {
+            pin: Rc::new(RefCell::new(Pin::new(self.uuid, number, pin_type))),
+            trace,
+            driver,
+        });
+        Ok(number)
+    }
+}
+
+impl std::fmt::Debug for SubBoard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubBoard")
+            .field("uuid", &self.uuid)
+            .field("chip_type", &self.chip_type)
+            .field("board", &self.board)
+            .finish()
+    }
+}
+
+impl Chip for SubBoard {
+    fn get_uuid(&self) -> u128 {
+        self.uuid
+    }
+    fn get_type(&self) -> &str {
+        &self.chip_type
+    }
+    fn get_pin_qty(&self) -> u8 {
+        self.ports.len() as u8
+    }
+
+    fn _get_pin(&mut self, pin: u8) -> Rc<RefCell<Pin>> {
+        self.ports[pin as usize - 1].pin.clone()
+    }
+
+    fn get_info(&self) -> ChipInfo {
+        let mut data = String::new();
+        for (index, port) in self.ports.iter().enumerate() {
+            data.push_str(&format!(
+                "{} {}: {}\n",
+                if port.driver.is_some() { "in " } else { "out" },
+                index + 1,
+                port.trace.borrow().get_name().unwrap_or("?")
+            ));
+        }
+        ChipInfo {
+            name: "Sub-board",
+            description: "A board packaged as a chip, its pins are traces of the inner board",
+            data,
+        }
+    }
+
+    fn set_logic_model(&mut self, model: LogicModel) {
+        self.board.set_logic_model(model);
+    }
+
+    fn run(&mut self, elapsed_time: std::time::Duration) {
+        for port in &self.ports {
+            if let Some(driver) = &port.driver {
+                driver.borrow_mut().state = port.pin.borrow().state.clone();
+            }
+        }
+        self.board.run(elapsed_time);
+        for port in &self.ports {
+            if port.driver.is_none() {
+                port.pin.borrow_mut().state = port.trace.borrow().get_state();
+            }
+        }
+    }
+
+    fn save_data(&self) -> Result<ChipData> {
+        let mut saved = self.board.to_saved()?;
+        saved.renumber();
+        to_data(&saved)
+    }
+    fn load_data(&mut self, chip_data: &ChipData) -> Result<()> {
+        let mut saved: SavedBoard = from_data(&self.chip_type, chip_data)?;
+        saved.migrate()?;
+        saved.restore_into(&self.board, &self.chip_type)
+    }
+    fn snapshot(&self) -> Box<dyn Any> {
+        Box::new(SubBoardSnapshot {
+            board: self.board.snapshot(),
+            pins: self
+                .ports
+                .iter()
+                .map(|port| {
+                    let pin = port.pin.borrow();
+                    (pin.pin_type.clone(), pin.state.clone())
+                })
+                .collect(),
+        })
+    }
+    fn restore(&mut self, snapshot: &dyn Any) -> Result<()> {
+        let snapshot = snapshot
+            .downcast_ref::<SubBoardSnapshot>()
+            .ok_or_else(|| Error::invalid_snapshot(&self.chip_type))?;
+        self.board.restore(&snapshot.board)?;
+        for (port, (pin_type, state)) in self.ports.iter().zip(&snapshot.pins) {
+            let mut pin = port.pin.borrow_mut();
+            pin.pin_type = pin_type.clone();
+            pin.state = state.clone();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chip::{gates::GateAnd, ChipRegistry},
+        FORMAT_VERSION,
+    };
+
+    fn inverter() -> SubBoard {
+        let board = Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part U1 virt_ic::GateNot
+             net VCC PWR.1 U1.14
+             net GND PWR.2 U1.7
+             net A U1.1
+             net Y U1.2",
+            &ChipRegistry::new(),
+        )
+        .unwrap();
+        let mut chip = SubBoard::new("test::Inverter", board);
+        chip.expose_input("A").unwrap();
+        chip.expose_output("Y").unwrap();
+        chip
+    }
+
+    #[test]
+    fn the_logic_model_reaches_the_inner_board() {
+        let mut board = Board::new();
+        let socket = board.new_socket_with(Box::new(inverter()));
+        board.set_logic_model(LogicModel::XPropagation);
+        let mut socket = socket.borrow_mut();
+        let chip = socket.get_chip_as::<SubBoard>().unwrap();
+        assert_eq!(chip.get_board().get_logic_model(), LogicModel::XPropagation);
+    }
+
+    #[test]
+    fn saved_data_is_migrated() {
+        let chip = inverter();
+        let mut data = chip.save_data().unwrap();
+        data["version"] = ChipData::from(0);
+        inverter().load_data(&data).unwrap();
+        data["version"] = ChipData::from(FORMAT_VERSION + 1);
+        assert!(matches!(
+            inverter().load_data(&data),
+            Err(Error::UnsupportedVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn saves_need_the_same_inner_board() {
+        let data = inverter().save_data().unwrap();
+        let mut empty = SubBoard::new("test::Inverter", Board::new());
+        assert!(matches!(
+            empty.load_data(&data),
+            Err(Error::InvalidChipData { chip_type, .. }) if chip_type == "test::Inverter"
+        ));
+
+        let mut swapped = inverter();
+        let socket = swapped.get_board().get_socket_by_name("U1").unwrap();
+        socket.borrow_mut().plug(Box::new(GateAnd::new()));
+        assert!(matches!(
+            swapped.load_data(&data),
+            Err(Error::InvalidChipData { .. })
+        ));
+    }
+}
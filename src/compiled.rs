@@ -0,0 +1,896 @@
+//! Compiled gate networks
+//!
+//! `Board::compile` turns the built-in gate chips of a board into a flat list of instructions,
+//! sorted by level so that every gate comes after the gates driving its inputs.
+//! Evaluating it settles the whole gate network in a single pass over a bit vector,
+//! without going through the pins, the traces and the chips of the board.
+//!
+//! The traces that no gate drives, or that are forced, are the inputs of the network:
+//! they're read from the board by `CompiledNetwork::load` or set with `CompiledNetwork::set_input`.
+//! Once evaluated, `CompiledNetwork::store` writes the outputs of the gates back to the board,
+//! which is then in the state `Board::settle` would have led it to.
+//! ```
+//! use virt_ic::{chip::ChipRegistry, Board, State};
+//!
+//! let mut board = Board::from_netlist(
+//!     "part PWR virt_ic::Generator
+//!      part U1 virt_ic::GateNand
+//!      net VCC PWR.1 U1.14
+//!      net GND PWR.2 U1.7
+//!      net A U1.1
+//!      net B U1.2
+//!      net Y U1.3",
+//!     &ChipRegistry::new(),
+//! )
+//! .unwrap();
+//! let mut network = board.compile().unwrap();
+//! network.load();
+//! network.set_input("A", State::High).unwrap();
+//! network.set_input("B", State::High).unwrap();
+//! network.eval();
+//! assert_eq!(network.get_state("Y"), Some(State::Low));
+//! ```
+//! `CompiledNetwork::eval_lanes` evaluates up to 64 input vectors at once, packed into the bits of `u64` words,
+//! which `CompiledNetwork::truth_table` uses to compute a truth table 64 rows at a time.
+//!
+//! Boards with the `XPropagation` logic model compile too, their gates then propagate Undefined.
+//!
+//! Traces are named like in the netlist: named traces keep their name, the others are `N1`, `N2`, ...
+//!
+//! The network only follows the traces and the forced states the board had when compiled:
+//! it has to be compiled again once they change.
+//! The pins of the gate chips that aren't linked to any trace keep the state they had when compiled.
+use super::{
+    chip::gates::{self, GateKind},
+    naming,
+    truth_table::{self, TruthTable},
+    Board, Chip, Error, LogicModel, Pin, PinType, Result, State, Trace,
+};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::rc::Rc;
+
+/// Number of input vectors evaluated at once by `CompiledNetwork::eval_lanes`
+pub const LANES: usize = 64;
+
+/// Every built-in gate chip is powered through these pins
+const VCC: u8 = 14;
+const GND: u8 = 7;
+
+/// A packed vector of bits
+#[derive(Debug, Clone, Default)]
+struct BitVec {
+    words: Vec<u64>,
+}
+
+impl BitVec {
+    fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    fn get(&self, index: usize) -> bool {
+        self.words[index / 64] >> (index % 64) & 1 == 1
+    }
+
+    fn set(&mut self, index: usize, value: bool) {
+        if value {
+            self.words[index / 64] |= 1 << (index % 64);
+        } else {
+            self.words[index / 64] &= !(1 << (index % 64));
+        }
+    }
+}
+
+/// A single gate of a gate chip
+#[derive(Debug)]
+struct Instruction {
+    kind: GateKind,
+    /// Range of its input nets in `CompiledNetwork::operands`
+    inputs: Range<usize>,
+    output: usize,
+    vcc: usize,
+    gnd: usize,
+    /// State of the output when the chip isn't powered
+    unpowered: State,
+    /// Undefined inputs are read as High
+    undefined_as_high: bool,
+    /// Output pin of the gate on the board
+    pin: Rc<RefCell<Pin>>,
+}
+
+/// The gate network of a board, compiled into levelized instructions
+///
+/// Nets are stored as two bit vectors: whether they're High, and whether they're defined.
+/// The first nets are the traces of the board, in order.
+#[derive(Debug)]
+pub struct CompiledNetwork {
+    traces: Vec<Rc<RefCell<Trace>>>,
+    /// Names of the traces, like in the netlist
+    labels: Vec<String>,
+    names: HashMap<String, usize>,
+    /// Nets read from the board
+    inputs: Vec<usize>,
+    instructions: Vec<Instruction>,
+    operands: Vec<usize>,
+    levels: usize,
+    /// Gates propagate Undefined
+    x_propagation: bool,
+    net_count: usize,
+    high: BitVec,
+    defined: BitVec,
+    /// States driven by each instruction
+    driven_high: BitVec,
+    driven_defined: BitVec,
+}
+
+/// Allocates the nets of a network
+struct Nets {
+    count: usize,
+    constants: Vec<(usize, State)>,
+}
+
+impl Nets {
+    fn add(&mut self) -> usize {
+        self.count += 1;
+        self.count - 1
+    }
+
+    /// Get a net holding a constant state, for the pins that aren't linked to any trace
+    fn constant(&mut self, state: State) -> usize {
+        match self
+            .constants
+            .iter()
+            .find(|(_, constant)| *constant == state)
+        {
+            Some((net, _)) => *net,
+            None => {
+                let net = self.add();
+                self.constants.push((net, state));
+                net
+            }
+        }
+    }
+}
+
+impl CompiledNetwork {
+    pub(crate) fn compile(board: &Board) -> Result<Self> {
+        let model = board.get_logic_model();
+        if model == LogicModel::StdLogic {
+            return Err(Error::InvalidNetwork(String::from(
+                "boards with the `StdLogic` model can't be compiled",
+            )));
+        }
+        let traces = board.get_traces();
+        let labels = naming::trace_names(board);
+        let mut nets = Nets {
+            count: traces.len(),
+            constants: vec![],
+        };
+        let mut pin_nets: HashMap<(u128, u8), usize> = HashMap::new();
+        for (net, trace) in traces.iter().enumerate() {
+            for pin in trace.borrow().get_pins() {
+                let pin = pin.borrow();
+                pin_nets.entry((pin.parent, pin.number)).or_insert(net);
+            }
+        }
+        let forced: Vec<bool> = traces
+            .iter()
+            .map(|trace| trace.borrow().get_forced().is_some())
+            .collect();
+
+        let mut gate_chips = HashSet::new();
+        let mut instructions = vec![];
+        let mut operands = vec![];
+        for socket in board.get_sockets() {
+            let mut socket = socket.borrow_mut();
+            let layout = match gates::get_gates(socket.get_type()) {
+                Some(layout) => layout,
+                None => continue,
+            };
+            let uuid = socket.get_uuid();
+            gate_chips.insert(uuid);
+            let quirks = gates::get_quirks(socket.get_type());
+            let net_of = |nets: &mut Nets, socket: &mut dyn Chip, number: u8| match pin_nets
+                .get(&(uuid, number))
+            {
+                Some(net) => *net,
+                None => nets.constant(socket.get_pin_state(number)),
+            };
+            let vcc = net_of(&mut nets, &mut *socket, VCC);
+            let gnd = net_of(&mut nets, &mut *socket, GND);
+            for gate in layout {
+                let start = operands.len();
+                for input in gate.inputs {
+                    operands.push(net_of(&mut nets, &mut *socket, *input));
+                }
+                // a gate driving a forced trace, or no trace, drives a net of its own
+                let output = match pin_nets.get(&(uuid, gate.output)) {
+                    Some(net) if !forced[*net] => *net,
+                    _ => nets.add(),
+                };
+                instructions.push(Instruction {
+                    kind: gate.kind,
+                    inputs: start..operands.len(),
+                    output,
+                    vcc,
+                    gnd,
+                    unpowered: quirks.unpowered.clone(),
+                    undefined_as_high: quirks.undefined_as_high,
+                    pin: socket.get_pin(gate.output)?,
+                });
+            }
+        }
+
+        let mut driven = vec![false; nets.count];
+        for instruction in &instructions {
+            driven[instruction.output] = true;
+        }
+        for (net, trace) in traces.iter().enumerate() {
+            if !driven[net] {
+                continue;
+            }
+            let external = trace.borrow().get_pins().iter().any(|pin| {
+                let pin = pin.borrow();
+                pin.pin_type == PinType::Output && !gate_chips.contains(&pin.parent)
+            });
+            if external {
+                return Err(Error::InvalidNetwork(format!(
+                    "`{}` is driven by gates and other chips",
+                    labels[net]
+                )));
+            }
+        }
+
+        let (instructions, levels) = levelize(instructions, &operands, nets.count, &labels)?;
+        let mut network = Self {
+            names: labels
+                .iter()
+                .enumerate()
+                .map(|(net, label)| (label.clone(), net))
+                .collect(),
+            labels,
+            inputs: (0..traces.len()).filter(|net| !driven[*net]).collect(),
+            traces,
+            driven_high: BitVec::new(instructions.len()),
+            driven_defined: BitVec::new(instructions.len()),
+            instructions,
+            operands,
+            levels,
+            x_propagation: model == LogicModel::XPropagation,
+            net_count: nets.count,
+            high: BitVec::new(nets.count),
+            defined: BitVec::new(nets.count),
+        };
+        for (net, state) in nets.constants {
+            network.set(net, &state);
+        }
+        network.load();
+        Ok(network)
+    }
+
+    /// Get the number of gates of the network
+    pub fn get_gate_count(&self) -> usize {
+        self.instructions.len()
+    }
+
+    /// Get the number of gates on the longest path from an input to an output
+    pub fn get_depth(&self) -> usize {
+        self.levels
+    }
+
+    /// Get the names of the traces read from the board, the inputs of the network
+    pub fn get_inputs(&self) -> Vec<String> {
+        self.inputs
+            .iter()
+            .map(|net| self.labels[*net].clone())
+            .collect()
+    }
+
+    /// Read the states of the inputs from the traces of the board
+    pub fn load(&mut self) {
+        for index in 0..self.inputs.len() {
+            let net = self.inputs[index];
+            let state = self.traces[net].borrow().get_state();
+            self.set(net, &state);
+        }
+    }
+
+    /// Set the state of an input, by trace name
+    pub fn set_input(&mut self, name: &str, state: State) -> Result<()> {
+        let net = self.get_input(name)?;
+        self.set(net, &state);
+        Ok(())
+    }
+
+    /// Get the state of a trace, by name
+    pub fn get_state(&self, name: &str) -> Option<State> {
+        self.names.get(name).map(|net| self.get(*net))
+    }
+
+    /// Settle the gate network from the states of its inputs
+    pub fn eval(&mut self) {
+        let Self {
+            instructions,
+            operands,
+            x_propagation,
+            high,
+            defined,
+            driven_high,
+            driven_defined,
+            ..
+        } = self;
+        for instruction in instructions.iter() {
+            high.set(instruction.output, false);
+            defined.set(instruction.output, false);
+        }
+        for (index, instruction) in instructions.iter().enumerate() {
+            let (output_high, output_defined) = drive(
+                instruction,
+                operands,
+                *x_propagation,
+                &|net| broadcast(high.get(net)),
+                &|net| broadcast(defined.get(net)),
+            );
+            let (output_high, output_defined) = (output_high & 1 == 1, output_defined & 1 == 1);
+            driven_high.set(index, output_high);
+            driven_defined.set(index, output_defined);
+            // a trace is High if one of its drivers is, Low if one of them is defined
+            if output_high {
+                high.set(instruction.output, true);
+            }
+            if output_defined {
+                defined.set(instruction.output, true);
+            }
+        }
+    }
+
+    /// Write the outputs of the gates to their pins, and the traces to the pins they link
+    pub fn store(&self) {
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            instruction.pin.borrow_mut().state =
+                to_state(self.driven_high.get(index), self.driven_defined.get(index));
+        }
+        for trace in &self.traces {
+            trace.borrow_mut().communicate();
+        }
+    }
+
+    /// Read the inputs from the board, settle the network and write its outputs back to the board
+    pub fn run(&mut self) {
+        self.load();
+        self.eval();
+        self.store();
+    }
+
+    /// Evaluate up to 64 input vectors at once, one per bit of the words, called lanes
+    ///
+    /// `inputs` gives the bits of some inputs by trace name, bit `n` being their state in lane `n`.
+    /// The other inputs keep their state in every lane.
+    /// ```
+    /// use virt_ic::{chip::ChipRegistry, Board};
+    ///
+    /// let board = Board::from_netlist(
+    ///     "part PWR virt_ic::Generator
+    ///      part U1 virt_ic::GateAnd
+    ///      net VCC PWR.1 U1.14
+    ///      net GND PWR.2 U1.7
+    ///      net A U1.1
+    ///      net B U1.2
+    ///      net Y U1.3",
+    ///     &ChipRegistry::new(),
+    /// )
+    /// .unwrap();
+    /// let network = board.compile().unwrap();
+    /// let lanes = network.eval_lanes(&[("A", 0b1010), ("B", 0b1100)]).unwrap();
+    /// assert_eq!(lanes.get_high("Y").unwrap() & 0b1111, 0b1000);
+    /// ```
+    pub fn eval_lanes(&self, inputs: &[(&str, u64)]) -> Result<Lanes<'_>> {
+        let inputs = inputs
+            .iter()
+            .map(|(name, bits)| Ok((self.get_input(name)?, *bits)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self.eval_words(&inputs))
+    }
+
+    /// Compute the truth table of the network 64 rows at a time, see `Board::truth_table`
+    ///
+    /// The `inputs` must be inputs of the network, the `outputs` can be any trace.
+    pub fn truth_table(&self, inputs: &[&str], outputs: &[&str]) -> Result<TruthTable> {
+        truth_table::check_inputs(inputs.len())?;
+        let input_nets = inputs
+            .iter()
+            .map(|name| self.get_input(name))
+            .collect::<Result<Vec<_>>>()?;
+        let output_nets = outputs
+            .iter()
+            .map(|name| self.get_net(name))
+            .collect::<Result<Vec<_>>>()?;
+
+        let row_count = 1usize << inputs.len();
+        let mut rows = Vec::with_capacity(row_count);
+        for first_row in (0..row_count).step_by(LANES) {
+            // the lane `n` evaluates the row `first_row + n`
+            let words: Vec<(usize, u64)> = input_nets
+                .iter()
+                .enumerate()
+                .map(|(bit, net)| {
+                    let word = (0..LANES)
+                        .filter(|lane| (first_row + lane) >> bit & 1 == 1)
+                        .fold(0u64, |word, lane| word | 1 << lane);
+                    (*net, word)
+                })
+                .collect();
+            let lanes = self.eval_words(&words);
+            for lane in 0..LANES.min(row_count - first_row) {
+                rows.push(
+                    output_nets
+                        .iter()
+                        .map(|net| lanes.get(*net, lane))
+                        .collect(),
+                );
+            }
+        }
+        Ok(TruthTable::new(inputs, outputs, rows))
+    }
+
+    fn eval_words(&self, inputs: &[(usize, u64)]) -> Lanes<'_> {
+        let mut high: Vec<u64> = (0..self.net_count)
+            .map(|net| broadcast(self.high.get(net)))
+            .collect();
+        let mut defined: Vec<u64> = (0..self.net_count)
+            .map(|net| broadcast(self.defined.get(net)))
+            .collect();
+        for (net, bits) in inputs {
+            high[*net] = *bits;
+            defined[*net] = u64::MAX;
+        }
+        for instruction in &self.instructions {
+            high[instruction.output] = 0;
+            defined[instruction.output] = 0;
+        }
+        for instruction in &self.instructions {
+            let (output_high, output_defined) = drive(
+                instruction,
+                &self.operands,
+                self.x_propagation,
+                &|net| high[net],
+                &|net| defined[net],
+            );
+            high[instruction.output] |= output_high;
+            defined[instruction.output] |= output_defined;
+        }
+        Lanes {
+            names: &self.names,
+            high,
+            defined,
+        }
+    }
+
+    fn get_net(&self, name: &str) -> Result<usize> {
+        self.names
+            .get(name)
+            .copied()
+            .ok_or_else(|| Error::UnknownTrace(String::from(name)))
+    }
+
+    fn get_input(&self, name: &str) -> Result<usize> {
+        let net = self.get_net(name)?;
+        if !self.inputs.contains(&net) {
+            return Err(Error::InvalidNetwork(format!(
+                "`{name}` is driven by gates, it isn't an input"
+            )));
+        }
+        Ok(net)
+    }
+
+    fn get(&self, net: usize) -> State {
+        to_state(self.high.get(net), self.defined.get(net))
+    }
+
+    fn set(&mut self, net: usize, state: &State) {
+        self.high.set(net, *state == State::High);
+        self.defined.set(net, *state != State::Undefined);
+    }
+}
+
+/// The states of the traces of a network for 64 input vectors, see `CompiledNetwork::eval_lanes`
+#[derive(Debug, Clone)]
+pub struct Lanes<'a> {
+    names: &'a HashMap<String, usize>,
+    high: Vec<u64>,
+    defined: Vec<u64>,
+}
+
+impl Lanes<'_> {
+    /// Get the lanes where a trace is High, by name
+    pub fn get_high(&self, name: &str) -> Option<u64> {
+        self.names.get(name).map(|net| self.high[*net])
+    }
+
+    /// Get the lanes where a trace is Low or High, by name
+    pub fn get_defined(&self, name: &str) -> Option<u64> {
+        self.names.get(name).map(|net| self.defined[*net])
+    }
+
+    /// Get the state of a trace in a lane, by name
+    pub fn get_state(&self, name: &str, lane: usize) -> Option<State> {
+        self.names.get(name).map(|net| self.get(*net, lane))
+    }
+
+    fn get(&self, net: usize, lane: usize) -> State {
+        to_state(
+            self.high[net] >> lane & 1 == 1,
+            self.defined[net] >> lane & 1 == 1,
+        )
+    }
+}
+
+fn broadcast(bit: bool) -> u64 {
+    if bit {
+        u64::MAX
+    } else {
+        0
+    }
+}
+
+/// Compute the states a gate drives in every lane, as the bits where it's High and where it's defined
+fn drive(
+    instruction: &Instruction,
+    operands: &[usize],
+    x_propagation: bool,
+    high: &dyn Fn(usize) -> u64,
+    defined: &dyn Fn(usize) -> u64,
+) -> (u64, u64) {
+    let powered = defined(instruction.gnd) & !high(instruction.gnd) & high(instruction.vcc);
+    let inputs = operands[instruction.inputs.clone()]
+        .iter()
+        .map(|net| (high(*net), defined(*net)));
+    let inverted = matches!(
+        instruction.kind,
+        GateKind::Nand | GateKind::Nor | GateKind::Not
+    );
+    let (output_high, output_low) = if x_propagation {
+        // the bits where the AND or the OR is surely High, and surely Low
+        let (is_high, is_low) = match instruction.kind {
+            GateKind::And | GateKind::Nand | GateKind::Not => inputs
+                .fold((u64::MAX, 0), |(is_high, is_low), (high, defined)| {
+                    (is_high & high, is_low | (defined & !high))
+                }),
+            GateKind::Or | GateKind::Nor => inputs
+                .fold((0, u64::MAX), |(is_high, is_low), (high, defined)| {
+                    (is_high | high, is_low & defined & !high)
+                }),
+        };
+        if inverted {
+            (is_low, is_high)
+        } else {
+            (is_high, is_low)
+        }
+    } else {
+        let undefined_as_high = broadcast(instruction.undefined_as_high);
+        let inputs = inputs.map(|(high, defined)| high | (undefined_as_high & !defined));
+        let output = match instruction.kind {
+            GateKind::And | GateKind::Nand | GateKind::Not => {
+                inputs.fold(u64::MAX, |output, input| output & input)
+            }
+            GateKind::Or | GateKind::Nor => inputs.fold(0, |output, input| output | input),
+        };
+        let output = if inverted { !output } else { output };
+        (output, !output)
+    };
+    let unpowered = broadcast(instruction.unpowered == State::Low);
+    (
+        powered & output_high,
+        (powered & (output_high | output_low)) | (!powered & unpowered),
+    )
+}
+
+fn to_state(high: bool, defined: bool) -> State {
+    match (high, defined) {
+        (true, _) => State::High,
+        (false, true) => State::Low,
+        (false, false) => State::Undefined,
+    }
+}
+
+/// Sort the instructions so that the drivers of a net come before its readers
+/// Returns the sorted instructions and the number of levels, or an error on a combinational loop.
+fn levelize(
+    instructions: Vec<Instruction>,
+    operands: &[usize],
+    net_count: usize,
+    labels: &[String],
+) -> Result<(Vec<Instruction>, usize)> {
+    let mut drivers = vec![vec![]; net_count];
+    for (index, instruction) in instructions.iter().enumerate() {
+        drivers[instruction.output].push(index);
+    }
+    // readers[i] are the instructions reading a net driven by instruction i
+    let mut readers = vec![vec![]; instructions.len()];
+    let mut sources = vec![];
+    let mut pending = vec![0usize; instructions.len()];
+    for (index, instruction) in instructions.iter().enumerate() {
+        let mut drivers: Vec<usize> = operands[instruction.inputs.clone()]
+            .iter()
+            .chain([&instruction.vcc, &instruction.gnd])
+            .flat_map(|net| drivers[*net].iter().copied())
+            .collect();
+        drivers.sort_unstable();
+        drivers.dedup();
+        pending[index] = drivers.len();
+        for source in &drivers {
+            readers[*source].push(index);
+        }
+        sources.push(drivers);
+    }
+
+    let mut levels = vec![0usize; instructions.len()];
+    let mut ready: Vec<usize> = (0..instructions.len())
+        .filter(|index| pending[*index] == 0)
+        .collect();
+    let mut order = Vec::with_capacity(instructions.len());
+    while let Some(index) = ready.pop() {
+        order.push(index);
+        for reader in &readers[index] {
+            levels[*reader] = levels[*reader].max(levels[index] + 1);
+            pending[*reader] -= 1;
+            if pending[*reader] == 0 {
+                ready.push(*reader);
+            }
+        }
+    }
+    if let Some(mut index) = (0..instructions.len()).find(|index| pending[*index] > 0) {
+        // go up the unresolved drivers until reaching the loop itself
+        for _ in 0..instructions.len() {
+            if let Some(source) = sources[index].iter().find(|source| pending[**source] > 0) {
+                index = *source;
+            }
+        }
+        let net = instructions[index].output;
+        let looped = labels
+            .get(net)
+            .map_or_else(|| String::from("a gate output"), |name| format!("`{name}`"));
+        return Err(Error::InvalidNetwork(format!(
+            "{looped} is part of a combinational loop"
+        )));
+    }
+
+    order.sort_by_key(|index| levels[*index]);
+    let depth = order.last().map_or(0, |index| levels[*index] + 1);
+    let mut instructions: Vec<Option<Instruction>> = instructions.into_iter().map(Some).collect();
+    let sorted = order
+        .into_iter()
+        .filter_map(|index| instructions[index].take())
+        .collect();
+    Ok((sorted, depth))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip::{gates::*, ChipRegistry};
+
+    const GATE_TYPES: [&str; 8] = [
+        GateOr::TYPE,
+        GateAnd::TYPE,
+        Gate3InputAnd::TYPE,
+        GateNot::TYPE,
+        GateNor::TYPE,
+        Gate3InputNor::TYPE,
+        GateNand::TYPE,
+        Gate3InputNand::TYPE,
+    ];
+
+    /// A board with a powered gate chip of each type, a net per gate pin
+    /// The inputs of the first gate of each chip are returned, and the outputs of every gate.
+    fn gate_board() -> (Board, Vec<String>, Vec<String>) {
+        let mut netlist = String::from("part PWR virt_ic::Generator\n");
+        let mut inputs = vec![];
+        let mut outputs = vec![];
+        for (chip, chip_type) in GATE_TYPES.iter().enumerate() {
+            netlist += &format!("part U{chip} {chip_type}\n");
+            netlist += &format!("net VCC PWR.1 U{chip}.14\nnet GND PWR.2 U{chip}.7\n");
+            for (index, gate) in gates::get_gates(chip_type).unwrap().iter().enumerate() {
+                for input in gate.inputs {
+                    let name = format!("U{chip}_{input}");
+                    netlist += &format!("net {name} U{chip}.{input}\n");
+                    if index == 0 {
+                        inputs.push(name);
+                    }
+                }
+                let name = format!("U{chip}_{}", gate.output);
+                netlist += &format!("net {name} U{chip}.{}\n", gate.output);
+                outputs.push(name);
+            }
+        }
+        let board = Board::from_netlist(&netlist, &ChipRegistry::new()).unwrap();
+        (board, inputs, outputs)
+    }
+
+    fn compare(board: &mut Board, inputs: &[String], outputs: &[String]) {
+        let inputs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+        let outputs: Vec<&str> = outputs.iter().map(String::as_str).collect();
+        let compiled = board
+            .compile()
+            .unwrap()
+            .truth_table(&inputs, &outputs)
+            .unwrap();
+        let interpreted = board.truth_table(&inputs, &outputs).unwrap();
+        assert_eq!(compiled, interpreted);
+    }
+
+    #[test]
+    fn gates_match_the_interpreter() {
+        let (mut board, inputs, outputs) = gate_board();
+        // one chip at a time, the truth table has at most 16 inputs
+        for chip in 0..GATE_TYPES.len() {
+            let prefix = format!("U{chip}_");
+            let select = |names: &[String]| -> Vec<String> {
+                names
+                    .iter()
+                    .filter(|name| name.starts_with(&prefix))
+                    .cloned()
+                    .collect()
+            };
+            compare(&mut board, &select(&inputs), &select(&outputs));
+        }
+    }
+
+    #[test]
+    fn floating_inputs_match_the_interpreter() {
+        for model in [LogicModel::Binary, LogicModel::XPropagation] {
+            let (mut board, inputs, outputs) = gate_board();
+            board.set_logic_model(model);
+            // the other gates keep their inputs floating
+            compare(&mut board, &inputs[..12], &outputs);
+        }
+    }
+
+    #[test]
+    fn chained_gates_match_the_interpreter() {
+        let mut board = Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part U1 virt_ic::GateNand
+             part U2 virt_ic::Gate3InputNor
+             net VCC PWR.1 U1.14 U2.14
+             net GND PWR.2 U1.7 U2.7
+             net A U1.1 U1.4
+             net B U1.2 U2.1
+             net C U1.5 U2.2
+             net X U1.3 U1.9
+             net Y U1.6 U1.10
+             net Z U1.8 U2.13
+             net OUT U2.12",
+            &ChipRegistry::new(),
+        )
+        .unwrap();
+        assert_eq!(board.compile().unwrap().get_depth(), 3);
+        let outputs = [String::from("X"), String::from("Z"), String::from("OUT")];
+        compare(
+            &mut board,
+            &[String::from("A"), String::from("B"), String::from("C")],
+            &outputs,
+        );
+    }
+
+    #[test]
+    fn loops_are_rejected() {
+        let board = Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part U1 virt_ic::GateNot
+             net VCC PWR.1 U1.14
+             net GND PWR.2 U1.7
+             net A U1.2 U1.3
+             net B U1.4 U1.1",
+            &ChipRegistry::new(),
+        )
+        .unwrap();
+        match board.compile() {
+            Err(Error::InvalidNetwork(message)) => {
+                assert!(message.ends_with("is part of a combinational loop"))
+            }
+            other => panic!("expected a loop, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unnamed_traces_are_named_like_the_netlist() {
+        let mut board = Board::new();
+        let gate = board.new_socket_with(Box::new(GateNot::new()));
+        for pin in [1, 2] {
+            let trace = board.new_trace();
+            trace
+                .borrow_mut()
+                .connect(gate.borrow_mut().get_pin(pin).unwrap());
+        }
+        let network = board.compile().unwrap();
+        assert_eq!(network.get_inputs(), vec![String::from("N1")]);
+        assert!(network.get_state("N2").is_some());
+        assert!(board.to_netlist().contains("net N1 "));
+    }
+
+    /// Two 3-input gates and a NAND reading both, 6 inputs and a depth of 2
+    fn lane_board() -> Board {
+        Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part U1 virt_ic::Gate3InputAnd
+             part U2 virt_ic::GateNand
+             net VCC PWR.1 U1.14 U2.14
+             net GND PWR.2 U1.7 U2.7
+             net A U1.1
+             net B U1.2
+             net C U1.13
+             net D U1.3
+             net E U1.4
+             net F U1.5
+             net X U1.12 U2.1
+             net Y U1.6 U2.2
+             net OUT U2.3",
+            &ChipRegistry::new(),
+        )
+        .unwrap()
+    }
+
+    const LANE_INPUTS: [&str; 6] = ["A", "B", "C", "D", "E", "F"];
+
+    #[test]
+    fn lanes_match_scalar_evaluations() {
+        let board = lane_board();
+        let network = board.compile().unwrap();
+        let mut scalar = board.compile().unwrap();
+        // pseudo-random input vectors, a different one in each lane
+        let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+        let words: Vec<(&str, u64)> = LANE_INPUTS
+            .iter()
+            .map(|name| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                (*name, seed)
+            })
+            .collect();
+        let lanes = network.eval_lanes(&words).unwrap();
+        for lane in 0..LANES {
+            for (name, word) in &words {
+                scalar
+                    .set_input(name, State::from(word >> lane & 1 == 1))
+                    .unwrap();
+            }
+            scalar.eval();
+            for name in ["X", "Y", "OUT"] {
+                assert_eq!(
+                    lanes.get_state(name, lane),
+                    scalar.get_state(name),
+                    "{} in lane {}",
+                    name,
+                    lane
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn partial_batches_match_scalar_evaluations() {
+        let board = lane_board();
+        let mut network = board.compile().unwrap();
+        // 2 and 5 inputs fill 4 and 32 lanes of a single batch, 6 inputs a full batch
+        for count in [2, 5, 6] {
+            let inputs = &LANE_INPUTS[..count];
+            let table = network.truth_table(inputs, &["OUT"]).unwrap();
+            assert_eq!(table.get_rows().len(), 1 << count);
+            for (row, states) in table.get_rows().iter().enumerate() {
+                network.load();
+                for (bit, name) in inputs.iter().enumerate() {
+                    network
+                        .set_input(name, State::from(row >> bit & 1 == 1))
+                        .unwrap();
+                }
+                network.eval();
+                assert_eq!(network.get_state("OUT").as_ref(), states.last());
+            }
+        }
+    }
+}
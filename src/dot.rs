@@ -0,0 +1,167 @@
+//! Graphviz DOT export of a board's connectivity
+//!
+//! Every chip is drawn as a record node whose ports are its pins, laid out like
+//! a DIP package. Traces linking two pins are drawn as edges, larger traces as a
+//! small hyperedge node connected to each of their pins.
+//! Nodes are identified by the position of their socket or trace, names are only labels,
+//! so that sockets or traces sharing a name are still drawn apart.
+use super::{naming, Board, Chip, PinType, State};
+use std::collections::HashMap;
+
+fn escape_id(id: &str) -> String {
+    id.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_record(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if "{}|<>\"\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn state_label(state: &State) -> &'static str {
+    match state {
+        State::High => "H",
+        State::Low => "L",
+        State::Undefined => "?",
+    }
+}
+
+/// Write the DOT graph of a board
+///
+/// If `with_states` is true, pins and traces are labelled with their current state.
+pub fn write(board: &Board, with_states: bool) -> String {
+    let mut output = String::from("graph board {\n");
+    output.push_str("    rankdir=LR;\n");
+    output.push_str("    node [shape=record];\n");
+
+    let mut nodes: HashMap<u128, String> = HashMap::new();
+    for (index, (socket, name)) in board
+        .get_sockets()
+        .iter()
+        .zip(naming::socket_names(board))
+        .enumerate()
+    {
+        let mut socket = socket.borrow_mut();
+        if !socket.has_chip() {
+            continue;
+        }
+        let pin_qty = socket.get_pin_qty();
+        let mut ports = vec![];
+        for number in 1..=pin_qty {
+            let mut label = number.to_string();
+            if let Ok(pin) = socket.get_pin(number) {
+                let pin = pin.borrow();
+                if with_states {
+                    label.push_str(&format!(
+                        "{}{}",
+                        match pin.pin_type {
+                            PinType::Input => " in ",
+                            PinType::Output => " out ",
+                            PinType::Undefined => " ",
+                        },
+                        state_label(&pin.state)
+                    ));
+                }
+            }
+            ports.push(format!("<p{number}> {}", escape_record(&label)));
+        }
+        // DIP layout: first half of the pins on the left, second half on the right going up
+        let half = ports.len() - ports.len() / 2;
+        let right: Vec<String> = ports.split_off(half).into_iter().rev().collect();
+        let node = format!("socket{}", index + 1);
+        output.push_str(&format!(
+            "    {node} [label=\"{{{{{}}}|{}\\n{}|{{{}}}}}\"];\n",
+            ports.join("|"),
+            escape_record(&name),
+            escape_record(socket.get_type()),
+            right.join("|")
+        ));
+        nodes.insert(socket.get_uuid(), node);
+    }
+
+    for (index, (trace, name)) in board
+        .get_traces()
+        .iter()
+        .zip(naming::trace_names(board))
+        .enumerate()
+    {
+        let trace = trace.borrow();
+        let endpoints: Vec<String> = trace
+            .get_pins()
+            .iter()
+            .filter_map(|pin| {
+                let pin = pin.borrow();
+                nodes
+                    .get(&pin.parent)
+                    .map(|node| format!("{node}:p{}", pin.number))
+            })
+            .collect();
+        let label = if with_states {
+            format!("{name} ({})", state_label(&trace.get_state()))
+        } else {
+            name.clone()
+        };
+        match endpoints.len() {
+            0 => {}
+            2 => output.push_str(&format!(
+                "    {} -- {} [label=\"{}\"];\n",
+                endpoints[0],
+                endpoints[1],
+                escape_id(&label)
+            )),
+            _ => {
+                let hyperedge = format!("trace{}", index + 1);
+                output.push_str(&format!(
+                    "    {hyperedge} [shape=point, xlabel=\"{}\"];\n",
+                    escape_id(&label)
+                ));
+                for endpoint in endpoints {
+                    output.push_str(&format!("    {hyperedge} -- {endpoint};\n"));
+                }
+            }
+        }
+    }
+    output.push_str("}\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip::gates::GateAnd;
+
+    #[test]
+    fn same_names_are_drawn_apart() {
+        let mut board = Board::new();
+        let gates = [
+            board.new_socket_with(Box::new(GateAnd::new())),
+            board.new_socket_with(Box::new(GateAnd::new())),
+        ];
+        for gate in &gates {
+            gate.borrow_mut().set_name("U1");
+            let trace = board.new_trace();
+            trace.borrow_mut().set_name("A");
+            for pin in 1..=3 {
+                trace
+                    .borrow_mut()
+                    .connect(gate.borrow_mut().get_pin(pin).unwrap());
+            }
+        }
+        let dot = write(&board, false);
+        for node in ["socket1", "socket2"] {
+            assert!(dot.contains(&format!("    {node} [label=\"{{{{<p1> 1|")));
+        }
+        for (trace, socket) in [("trace1", "socket1"), ("trace2", "socket2")] {
+            assert!(dot.contains(&format!("    {trace} [shape=point, xlabel=\"A\"];")));
+            for pin in 1..=3 {
+                assert!(dot.contains(&format!("    {trace} -- {socket}:p{pin};")));
+            }
+        }
+        assert_eq!(dot.matches(" -- ").count(), 6);
+    }
+}
@@ -0,0 +1,159 @@
+//! Errors of the crate
+use super::{
+    expression::ExpressionError, netlist::NetlistError, save::SaveFormat, verilog::VerilogError,
+};
+use std::fmt;
+use std::time::Duration;
+
+/// The error type of the crate
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing a file failed
+    Io(std::io::Error),
+    /// A save couldn't be serialized or deserialized
+    Ron(ron::Error),
+    Json(serde_json::Error),
+    /// A CBOR save couldn't be serialized or deserialized, with the message of the encoder or decoder
+    Cbor(String),
+    /// A binary save format was used where text is expected
+    NotTextFormat(SaveFormat),
+    /// A pin number outside of `1..=pin_qty`
+    PinOutOfBounds {
+        pin: u8,
+        pin_qty: u8,
+    },
+    /// A socket without any chip plugged in it
+    NoChip,
+    /// The `ChipRegistry` doesn't know this chip type
+    UnknownChipType(String),
+    /// No trace of the board has this name
+    UnknownTrace(String),
+    /// No socket of the board has this designator
+    UnknownSocket(String),
+    /// The saved data of a chip is missing or malformed
+    InvalidChipData {
+        chip_type: String,
+        message: String,
+    },
+    /// A snapshot doesn't match the board or the chip it's restored to
+    InvalidSnapshot(String),
+    /// The history is disabled or has no checkpoint at or before the requested time
+    NoCheckpoint(Duration),
+    /// The save was written with a newer format than this version of the crate supports
+    UnsupportedVersion {
+        version: u32,
+        supported: u32,
+    },
+    /// The definition of a `LogicTable`, a `Synthesizer` or a `TruthTable` is invalid
+    InvalidLogic(String),
+    Expression(ExpressionError),
+    /// The test bench can't be run
+    InvalidTestBench(String),
+    /// The fault can't be injected in the board
+    InvalidFault(String),
+    /// The gate network of the board can't be compiled
+    InvalidNetwork(String),
+    Netlist(NetlistError),
+    Verilog(VerilogError),
+}
+
+/// A `Result` with the crate's `Error`
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn invalid_chip_data(chip_type: &str, message: String) -> Self {
+        Self::InvalidChipData {
+            chip_type: String::from(chip_type),
+            message,
+        }
+    }
+
+    pub fn invalid_snapshot(chip_type: &str) -> Self {
+        Self::InvalidSnapshot(format!("not a snapshot of a `{chip_type}`"))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+            Self::Ron(err) => write!(f, "RON error: {err}"),
+            Self::Json(err) => write!(f, "JSON error: {err}"),
+            Self::Cbor(err) => write!(f, "CBOR error: {err}"),
+            Self::NotTextFormat(format) => write!(f, "{format:?} is not a text format"),
+            Self::PinOutOfBounds { pin, pin_qty } => {
+                write!(f, "pin {pin} out of bounds, the chip has {pin_qty} pins")
+            }
+            Self::NoChip => write!(f, "no chip connected"),
+            Self::UnknownChipType(chip_type) => write!(f, "unknown chip type `{chip_type}`"),
+            Self::UnknownTrace(name) => write!(f, "unknown trace `{name}`"),
+            Self::UnknownSocket(name) => write!(f, "unknown socket `{name}`"),
+            Self::InvalidChipData { chip_type, message } => {
+                write!(f, "invalid data for `{chip_type}`: {message}")
+            }
+            Self::InvalidSnapshot(message) => write!(f, "invalid snapshot: {message}"),
+            Self::NoCheckpoint(time) => write!(f, "no checkpoint to rewind to {time:?}"),
+            Self::UnsupportedVersion { version, supported } => write!(
+                f,
+                "save format version {version} is newer than the supported version {supported}"
+            ),
+            Self::InvalidLogic(message) => write!(f, "invalid logic: {message}"),
+            Self::Expression(err) => write!(f, "expression error: {err}"),
+            Self::InvalidTestBench(message) => write!(f, "invalid test bench: {message}"),
+            Self::InvalidFault(message) => write!(f, "invalid fault: {message}"),
+            Self::InvalidNetwork(message) => write!(f, "invalid gate network: {message}"),
+            Self::Netlist(err) => write!(f, "netlist error: {err}"),
+            Self::Verilog(err) => write!(f, "verilog error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Ron(err) => Some(err),
+            Self::Json(err) => Some(err),
+            Self::Expression(err) => Some(err),
+            Self::Netlist(err) => Some(err),
+            Self::Verilog(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::Error> for Error {
+    fn from(err: ron::Error) -> Self {
+        Self::Ron(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<ExpressionError> for Error {
+    fn from(err: ExpressionError) -> Self {
+        Self::Expression(err)
+    }
+}
+
+impl From<NetlistError> for Error {
+    fn from(err: NetlistError) -> Self {
+        Self::Netlist(err)
+    }
+}
+
+impl From<VerilogError> for Error {
+    fn from(err: VerilogError) -> Self {
+        Self::Verilog(err)
+    }
+}
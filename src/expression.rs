@@ -0,0 +1,315 @@
+//! Boolean expressions over named signals
+//!
+//! The syntax accepts `!` or `~` for NOT, `&` or `*` for AND, `^` for XOR and `|` or `+` for OR,
+//! from the highest to the lowest precedence, parentheses, the constants `0` and `1`
+//! and signal names made of letters, digits and `_`.
+//! ```text
+//! !A0 & !A1 | (B ^ 1)
+//! ```
+use std::fmt;
+
+/// An error encountered while parsing an expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpressionError {
+    /// Column of the expression where the error occured, starting at 1
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+/// A boolean expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    Const(bool),
+    Var(String),
+    Not(Box<Expression>),
+    And(Vec<Expression>),
+    Or(Vec<Expression>),
+    Xor(Vec<Expression>),
+}
+
+impl Expression {
+    /// Parse an expression
+    pub fn parse(input: &str) -> Result<Self, ExpressionError> {
+        let mut parser = Parser {
+            chars: input.chars().collect(),
+            position: 0,
+        };
+        let expression = parser.or()?;
+        parser.skip_whitespace();
+        if let Some(c) = parser.peek() {
+            return Err(parser.error(format!("unexpected `{c}`")));
+        }
+        Ok(expression)
+    }
+
+    /// Evaluate the expression, `value` gives the value of each signal
+    /// Returns `None` if a signal has no value.
+    pub fn eval(&self, value: &dyn Fn(&str) -> Option<bool>) -> Option<bool> {
+        Some(match self {
+            Self::Const(constant) => *constant,
+            Self::Var(name) => value(name)?,
+            Self::Not(expression) => !expression.eval(value)?,
+            Self::And(terms) => {
+                let mut result = true;
+                for term in terms {
+                    result &= term.eval(value)?;
+                }
+                result
+            }
+            Self::Or(terms) => {
+                let mut result = false;
+                for term in terms {
+                    result |= term.eval(value)?;
+                }
+                result
+            }
+            Self::Xor(terms) => {
+                let mut result = false;
+                for term in terms {
+                    result ^= term.eval(value)?;
+                }
+                result
+            }
+        })
+    }
+
+    /// Get the names of the signals used by the expression, sorted and without duplicates
+    pub fn variables(&self) -> Vec<String> {
+        let mut variables = vec![];
+        self.collect_variables(&mut variables);
+        variables.sort();
+        variables.dedup();
+        variables
+    }
+
+    fn collect_variables(&self, variables: &mut Vec<String>) {
+        match self {
+            Self::Const(_) => {}
+            Self::Var(name) => variables.push(name.clone()),
+            Self::Not(expression) => expression.collect_variables(variables),
+            Self::And(terms) | Self::Or(terms) | Self::Xor(terms) => {
+                for term in terms {
+                    term.collect_variables(variables);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, terms: &[Expression], operator: &str| {
+            for (index, term) in terms.iter().enumerate() {
+                if index > 0 {
+                    write!(f, " {operator} ")?;
+                }
+                match term {
+                    Self::Const(_) | Self::Var(_) | Self::Not(_) => write!(f, "{term}")?,
+                    _ => write!(f, "({term})")?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Self::Const(constant) => write!(f, "{}", u8::from(*constant)),
+            Self::Var(name) => write!(f, "{name}"),
+            Self::Not(expression) => match **expression {
+                Self::Const(_) | Self::Var(_) | Self::Not(_) => write!(f, "!{expression}"),
+                _ => write!(f, "!({expression})"),
+            },
+            Self::And(terms) => join(f, terms, "&"),
+            Self::Or(terms) => join(f, terms, "|"),
+            Self::Xor(terms) => join(f, terms, "^"),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn error(&self, message: String) -> ExpressionError {
+        ExpressionError {
+            column: self.position + 1,
+            message,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    /// Consume the next character if it's one of `operators`
+    fn eat(&mut self, operators: &[char]) -> bool {
+        self.skip_whitespace();
+        if self.peek().is_some_and(|c| operators.contains(&c)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Expression, ExpressionError> {
+        let mut terms = vec![self.xor()?];
+        while self.eat(&['|', '+']) {
+            terms.push(self.xor()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expression::Or(terms)
+        })
+    }
+
+    fn xor(&mut self) -> Result<Expression, ExpressionError> {
+        let mut terms = vec![self.and()?];
+        while self.eat(&['^']) {
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expression::Xor(terms)
+        })
+    }
+
+    fn and(&mut self) -> Result<Expression, ExpressionError> {
+        let mut terms = vec![self.not()?];
+        while self.eat(&['&', '*']) {
+            terms.push(self.not()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expression::And(terms)
+        })
+    }
+
+    fn not(&mut self) -> Result<Expression, ExpressionError> {
+        if self.eat(&['!', '~']) {
+            Ok(Expression::Not(Box::new(self.not()?)))
+        } else {
+            self.atom()
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expression, ExpressionError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                let expression = self.or()?;
+                if !self.eat(&[')']) {
+                    return Err(self.error(String::from("expected `)`")));
+                }
+                Ok(expression)
+            }
+            Some(c) if c.is_alphanumeric() || c == '_' => {
+                let start = self.position;
+                while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    self.position += 1;
+                }
+                let name: String = self.chars[start..self.position].iter().collect();
+                match name.as_str() {
+                    "0" => Ok(Expression::Const(false)),
+                    "1" => Ok(Expression::Const(true)),
+                    _ if name.starts_with(|c: char| c.is_ascii_digit()) => {
+                        self.position = start;
+                        Err(self.error(format!("invalid constant `{name}`, expected 0 or 1")))
+                    }
+                    _ => Ok(Expression::Var(name)),
+                }
+            }
+            Some(c) => Err(self.error(format!("unexpected `{c}`"))),
+            None => Err(self.error(String::from("unexpected end of expression"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Expression {
+        Expression::Var(String::from(name))
+    }
+
+    #[test]
+    fn operators_bind_from_not_to_or() {
+        assert_eq!(
+            Expression::parse("!A & B ^ C | D").unwrap(),
+            Expression::Or(vec![
+                Expression::Xor(vec![
+                    Expression::And(vec![Expression::Not(Box::new(var("A"))), var("B")]),
+                    var("C"),
+                ]),
+                var("D"),
+            ])
+        );
+        assert_eq!(
+            Expression::parse("~(A + B) * 1").unwrap(),
+            Expression::And(vec![
+                Expression::Not(Box::new(Expression::Or(vec![var("A"), var("B")]))),
+                Expression::Const(true),
+            ])
+        );
+    }
+
+    #[test]
+    fn expressions_evaluate() {
+        let expression = Expression::parse("A | B & !C").unwrap();
+        for input in 0..8 {
+            let value = |name: &str| match name {
+                "A" => Some(input & 1 != 0),
+                "B" => Some(input & 2 != 0),
+                "C" => Some(input & 4 != 0),
+                _ => None,
+            };
+            let expected = input & 1 != 0 || (input & 2 != 0 && input & 4 == 0);
+            assert_eq!(expression.eval(&value), Some(expected));
+        }
+        assert_eq!(expression.eval(&|_| None), None);
+    }
+
+    #[test]
+    fn displayed_expressions_parse_back() {
+        let expression = Expression::parse("!(A0 & B) ^ (C | 0)").unwrap();
+        assert_eq!(
+            Expression::parse(&expression.to_string()).unwrap(),
+            expression
+        );
+    }
+
+    #[test]
+    fn malformed_expressions_are_errors() {
+        let column = |input: &str| Expression::parse(input).unwrap_err().column;
+        assert_eq!(column(""), 1);
+        assert_eq!(column("   "), 4);
+        assert_eq!(column("(A & B"), 7);
+        assert_eq!(column("A & B)"), 6);
+        assert_eq!(column("A &"), 4);
+        assert_eq!(column("| A"), 1);
+        assert_eq!(column("A ! B"), 3);
+        assert_eq!(column("A $ B"), 3);
+        assert_eq!(column("A & 2"), 5);
+        assert_eq!(column("A & 01"), 5);
+        assert_eq!(column("()"), 2);
+    }
+}
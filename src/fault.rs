@@ -0,0 +1,433 @@
+//! Fault injection
+//!
+//! Faults are injected into a board with `Board::inject_fault` and applied while it runs,
+//! during a window of simulated time.
+//! `TestBench::run_with_faults` runs a test bench once without faults (the golden run),
+//! then once per fault, and reports the faults that changed the outcome of its steps:
+//! the fault coverage of the test sequence.
+use super::{
+    chip::{
+        clocks::{Clock100Hz, Clock1kHz},
+        memory::Ram256B,
+    },
+    testbench::{Report, Signal, TestBench},
+    Board, Chip, Error, Pin, Result, Socket, State, Trace,
+};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// The kind of a fault
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FaultKind {
+    /// A trace or a pin stuck at 1 (true) or 0 (false)
+    StuckAt(Signal, bool),
+    /// A bit of a `Ram256B` flipped once, at the start of the fault
+    BitFlip {
+        socket: String,
+        address: u8,
+        bit: u8,
+    },
+    /// The pulses of a clock dropped, its output being stuck at 0
+    DropClock(String),
+}
+
+/// A fault applied during a window of simulated time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fault {
+    pub kind: FaultKind,
+    pub from: Duration,
+    /// End of the fault, it lasts forever if `None`
+    pub until: Option<Duration>,
+}
+
+impl Fault {
+    pub fn stuck_at(signal: Signal, value: bool, from: Duration, until: Option<Duration>) -> Self {
+        Self {
+            kind: FaultKind::StuckAt(signal, value),
+            from,
+            until,
+        }
+    }
+
+    /// Flip a bit of a RAM, fails if the bit isn't between 0 and 7
+    pub fn bit_flip(socket: &str, address: u8, bit: u8, at: Duration) -> Result<Self> {
+        check_bit(bit)?;
+        Ok(Self {
+            kind: FaultKind::BitFlip {
+                socket: String::from(socket),
+                address,
+                bit,
+            },
+            from: at,
+            until: None,
+        })
+    }
+
+    pub fn drop_clock(socket: &str, from: Duration, until: Option<Duration>) -> Self {
+        Self {
+            kind: FaultKind::DropClock(String::from(socket)),
+            from,
+            until,
+        }
+    }
+
+    fn is_active(&self, time: Duration) -> bool {
+        time >= self.from && self.until.is_none_or(|until| time < until)
+    }
+
+    fn shifted(&self, offset: Duration) -> Self {
+        Self {
+            kind: self.kind.clone(),
+            from: self.from + offset,
+            until: self.until.map(|until| until + offset),
+        }
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            FaultKind::StuckAt(signal, value) => {
+                write!(f, "{signal} stuck at {}", u8::from(*value))?;
+            }
+            FaultKind::BitFlip {
+                socket,
+                address,
+                bit,
+            } => write!(f, "bit {bit} of {socket}[0x{address:02X}] flipped")?,
+            FaultKind::DropClock(socket) => write!(f, "{socket} pulses dropped")?,
+        }
+        write!(f, " from {:?}", self.from)?;
+        if let Some(until) = self.until {
+            write!(f, " until {until:?}")?;
+        }
+        Ok(())
+    }
+}
+
+fn check_bit(bit: u8) -> Result<()> {
+    if bit >= 8 {
+        return Err(Error::InvalidFault(format!(
+            "bit {bit} out of a byte, it must be between 0 and 7"
+        )));
+    }
+    Ok(())
+}
+
+/// What a fault acts on, resolved when it's injected
+#[derive(Debug, Clone)]
+enum Target {
+    /// A trace and the state it was forced to before the fault
+    Trace(Rc<RefCell<Trace>>, Option<State>),
+    Pin(Rc<RefCell<Pin>>),
+    Ram(Rc<RefCell<Socket>>),
+}
+
+/// A fault injected into a board
+#[derive(Debug, Clone)]
+pub(crate) struct InjectedFault {
+    fault: Fault,
+    target: Target,
+    applied: bool,
+}
+
+impl InjectedFault {
+    pub(crate) fn new(board: &Board, fault: Fault) -> Result<Self> {
+        let find_socket = |name: &str| {
+            board
+                .get_socket_by_name(name)
+                .ok_or_else(|| Error::UnknownSocket(String::from(name)))
+        };
+        let target = match &fault.kind {
+            FaultKind::StuckAt(Signal::Trace(name), _) => Target::Trace(
+                board
+                    .get_trace_by_name(name)
+                    .ok_or_else(|| Error::UnknownTrace(name.clone()))?,
+                None,
+            ),
+            FaultKind::StuckAt(Signal::Pin(socket, pin), _) => {
+                Target::Pin(find_socket(socket)?.borrow_mut().get_pin(*pin)?)
+            }
+            FaultKind::BitFlip {
+                socket: name, bit, ..
+            } => {
+                check_bit(*bit)?;
+                let socket = find_socket(name)?;
+                if socket.borrow_mut().get_chip_as::<Ram256B>().is_none() {
+                    return Err(Error::InvalidFault(format!("`{name}` isn't a RAM")));
+                }
+                Target::Ram(socket)
+            }
+            FaultKind::DropClock(name) => {
+                let socket = find_socket(name)?;
+                let mut socket = socket.borrow_mut();
+                let is_clock = socket.get_chip_as::<Clock100Hz>().is_some()
+                    || socket.get_chip_as::<Clock1kHz>().is_some();
+                if !is_clock {
+                    return Err(Error::InvalidFault(format!("`{name}` isn't a clock")));
+                }
+                // both clocks output on their pin 1
+                Target::Pin(socket.get_pin(Clock1kHz::CLK)?)
+            }
+        };
+        Ok(Self {
+            fault,
+            target,
+            applied: false,
+        })
+    }
+
+    /// Apply or remove the fault on the traces and memories, before the traces communicate
+    pub(crate) fn update(&mut self, time: Duration) {
+        let active = self.fault.is_active(time);
+        match (&mut self.target, &self.fault.kind) {
+            (Target::Trace(trace, previous), FaultKind::StuckAt(_, value)) => {
+                let state = State::from(*value);
+                if active {
+                    // keep what the trace is set to meanwhile, to put it back afterward
+                    let forced = trace.borrow().get_forced();
+                    if !self.applied || forced.as_ref() != Some(&state) {
+                        *previous = forced;
+                    }
+                    trace.borrow_mut().force(state);
+                    self.applied = true;
+                } else if self.applied {
+                    release(trace, previous.take());
+                    self.applied = false;
+                }
+            }
+            (Target::Ram(socket), FaultKind::BitFlip { address, bit, .. })
+                if active && !self.applied =>
+            {
+                if let Some(ram) = socket.borrow_mut().get_chip_as::<Ram256B>() {
+                    ram.get_ram_mut()[*address as usize] ^= 1 << bit;
+                }
+                self.applied = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Force the stuck pins, before and after the chips run
+    pub(crate) fn stick(&self, time: Duration) {
+        if let Target::Pin(pin) = &self.target {
+            if self.fault.is_active(time) {
+                let value = match &self.fault.kind {
+                    FaultKind::StuckAt(_, value) => *value,
+                    _ => false,
+                };
+                pin.borrow_mut().state = State::from(value);
+            }
+        }
+    }
+
+    /// Stop the fault, putting back the forced state of its trace
+    pub(crate) fn clear(self) {
+        if let (Target::Trace(trace, previous), true) = (self.target, self.applied) {
+            release(&trace, previous);
+        }
+    }
+}
+
+fn release(trace: &Rc<RefCell<Trace>>, previous: Option<State>) {
+    match previous {
+        Some(state) => trace.borrow_mut().force(state),
+        None => trace.borrow_mut().release(),
+    }
+}
+
+/// The steps of a test bench whose outcome a fault changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaultResult {
+    pub fault: Fault,
+    /// Indexes of the steps that didn't have the same outcome as in the golden run
+    pub changed_steps: Vec<usize>,
+}
+
+impl FaultResult {
+    /// Returns true if the test bench noticed the fault
+    pub fn is_detected(&self) -> bool {
+        !self.changed_steps.is_empty()
+    }
+}
+
+/// The results of a fault campaign
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaultReport {
+    /// The report of the run without fault
+    pub golden: Report,
+    pub results: Vec<FaultResult>,
+}
+
+impl FaultReport {
+    pub fn detected(&self) -> impl Iterator<Item = &FaultResult> {
+        self.results.iter().filter(|result| result.is_detected())
+    }
+
+    pub fn undetected(&self) -> impl Iterator<Item = &FaultResult> {
+        self.results.iter().filter(|result| !result.is_detected())
+    }
+
+    /// Get the ratio of the faults detected by the test bench, 1 without any fault
+    pub fn get_coverage(&self) -> f64 {
+        if self.results.is_empty() {
+            return 1.0;
+        }
+        self.detected().count() as f64 / self.results.len() as f64
+    }
+}
+
+impl fmt::Display for FaultReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            if result.is_detected() {
+                let steps: Vec<String> = result
+                    .changed_steps
+                    .iter()
+                    .map(|index| (index + 1).to_string())
+                    .collect();
+                writeln!(
+                    f,
+                    "detected    {} (steps {})",
+                    result.fault,
+                    steps.join(", ")
+                )?;
+            } else {
+                writeln!(f, "undetected  {}", result.fault)?;
+            }
+        }
+        write!(
+            f,
+            "coverage: {}/{} ({:.1}%)",
+            self.detected().count(),
+            self.results.len(),
+            self.get_coverage() * 100.0
+        )
+    }
+}
+
+/// Run a test bench without fault, then with each fault, starting from the same state
+/// The times of the faults are relative to the start of the test bench.
+pub(crate) fn campaign(
+    bench: &TestBench,
+    board: &mut Board,
+    faults: &[Fault],
+) -> Result<FaultReport> {
+    let start = board.get_elapsed();
+    let snapshot = board.snapshot();
+    let forced: Vec<Option<State>> = board
+        .get_traces()
+        .iter()
+        .map(|trace| trace.borrow().get_forced())
+        .collect();
+    // the faults injected before the campaign apply to every run
+    let injected = board.get_injected_faults();
+    let reset = |board: &mut Board| -> Result<()> {
+        board.clear_faults();
+        board.restore(&snapshot)?;
+        board.set_elapsed(start);
+        for (trace, state) in board.get_traces().iter().zip(&forced) {
+            release(trace, state.clone());
+        }
+        board.set_injected_faults(injected.clone());
+        Ok(())
+    };
+
+    let golden = bench.run(board);
+    let mut results = vec![];
+    for fault in faults {
+        reset(board)?;
+        board.inject_fault(fault.shifted(start))?;
+        let report = bench.run(board);
+        let changed_steps = golden
+            .results
+            .iter()
+            .zip(&report.results)
+            .filter(|(golden, faulty)| golden.outcome != faulty.outcome)
+            .map(|(golden, _)| golden.index)
+            .collect();
+        results.push(FaultResult {
+            fault: fault.clone(),
+            changed_steps,
+        });
+    }
+    reset(board)?;
+    Ok(FaultReport { golden, results })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chip::ChipRegistry, testbench::Step};
+
+    fn inverter() -> Board {
+        Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part U1 virt_ic::GateNot
+             net VCC PWR.1 U1.14
+             net GND PWR.2 U1.7
+             net A U1.1
+             net Y U1.2
+             net B U1.3",
+            &ChipRegistry::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn campaigns_keep_the_faults_injected_before() {
+        let mut board = inverter();
+        board
+            .inject_fault(Fault::stuck_at(
+                Signal::Trace(String::from("A")),
+                true,
+                Duration::ZERO,
+                None,
+            ))
+            .unwrap();
+        let bench = TestBench {
+            board: None,
+            step: 1000,
+            steps: vec![
+                Step::Run(2),
+                Step::Expect(Signal::Trace(String::from("Y")), State::Low),
+            ],
+        };
+        let unrelated =
+            Fault::stuck_at(Signal::Trace(String::from("B")), true, Duration::ZERO, None);
+        let report = bench.run_with_faults(&mut board, &[unrelated]).unwrap();
+        assert!(report.golden.passed());
+        assert_eq!(report.get_coverage(), 0.0);
+        assert_eq!(board.get_injected_faults().len(), 1);
+        board.run(Duration::from_millis(1));
+        let a = board.get_trace_by_name("A").unwrap();
+        assert_eq!(a.borrow().get_forced(), Some(State::High));
+    }
+
+    #[test]
+    fn bit_flips_stay_in_a_byte() {
+        assert!(Fault::bit_flip("RAM", 0, 7, Duration::ZERO).is_ok());
+        assert!(matches!(
+            Fault::bit_flip("RAM", 0, 8, Duration::ZERO),
+            Err(Error::InvalidFault(_))
+        ));
+        let mut board =
+            Board::from_netlist("part RAM virt_ic::Ram256B", &ChipRegistry::new()).unwrap();
+        let fault = Fault {
+            kind: FaultKind::BitFlip {
+                socket: String::from("RAM"),
+                address: 0,
+                bit: 9,
+            },
+            from: Duration::ZERO,
+            until: None,
+        };
+        assert!(matches!(
+            board.inject_fault(fault),
+            Err(Error::InvalidFault(_))
+        ));
+    }
+}
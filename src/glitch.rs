@@ -0,0 +1,244 @@
+//! Glitch detection
+//!
+//! Once enabled with `Board::enable_glitch_detection`, the board watches the state of its traces
+//! and records a `Glitch` when a trace pulses for less than a threshold (L -> H -> L or H -> L -> H),
+//! or changes more than once while `Board::settle` settles the circuit.
+//! Such glitches are the symptom of static hazards, like a decoder whose inverted input
+//! lags behind the direct one and selects two chips at once for a step.
+//!
+//! Pulses are measured in simulation steps: with a threshold larger than the step,
+//! a pulse lasting a single step is always reported.
+use super::{naming, Chip, PinType, Socket, State, Trace};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// The kind of a glitch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlitchKind {
+    /// The trace held `state` for less than the threshold before going back to its previous state
+    ShortPulse { state: State, duration: Duration },
+    /// The trace changed `count` times while the circuit settled
+    MultipleTransitions { count: usize },
+}
+
+/// A glitch observed on a trace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glitch {
+    /// Simulated time the glitch ended at
+    pub time: Duration,
+    /// Name of the trace, or its number if it has none
+    pub trace: String,
+    /// Output pins driving the trace, as `socket.pin`, the socket being named by its designator or chip type
+    pub drivers: Vec<String>,
+    pub kind: GlitchKind,
+}
+
+impl fmt::Display for Glitch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:?}] {}", self.time, self.trace)?;
+        if !self.drivers.is_empty() {
+            write!(f, " (driven by {})", self.drivers.join(", "))?;
+        }
+        match &self.kind {
+            GlitchKind::ShortPulse { state, duration } => {
+                write!(f, ": {state:?} pulse of {duration:?}")
+            }
+            GlitchKind::MultipleTransitions { count } => {
+                write!(f, ": {count} transitions while settling")
+            }
+        }
+    }
+}
+
+/// The last transitions of a trace
+#[derive(Debug, Clone)]
+struct TraceRecord {
+    state: State,
+    /// The state before the current one
+    previous: State,
+    since: Duration,
+    /// Number of transitions during the current settle
+    transitions: usize,
+}
+
+/// Watches the traces of a board for glitches
+#[derive(Debug)]
+pub(crate) struct GlitchDetector {
+    min_pulse: Duration,
+    records: Vec<TraceRecord>,
+    settling: bool,
+    pub(crate) glitches: Vec<Glitch>,
+}
+
+impl GlitchDetector {
+    pub(crate) fn new(min_pulse: Duration) -> Self {
+        Self {
+            min_pulse,
+            records: vec![],
+            settling: false,
+            glitches: vec![],
+        }
+    }
+
+    /// Compare the state of the traces with their last one, once they communicated
+    pub(crate) fn observe(
+        &mut self,
+        time: Duration,
+        traces: &[Rc<RefCell<Trace>>],
+        sockets: &[Rc<RefCell<Socket>>],
+    ) {
+        for (index, trace) in traces.iter().enumerate() {
+            let state = trace.borrow().get_state();
+            if index >= self.records.len() {
+                self.records.push(TraceRecord {
+                    state,
+                    previous: State::Undefined,
+                    since: time,
+                    transitions: 0,
+                });
+                continue;
+            }
+            let record = &mut self.records[index];
+            if record.state == state {
+                continue;
+            }
+            let duration = time.saturating_sub(record.since);
+            let pulse = record.previous == state && duration < self.min_pulse;
+            let pulse_state = std::mem::replace(&mut record.state, state);
+            record.previous = pulse_state.clone();
+            record.since = time;
+            if self.settling {
+                record.transitions += 1;
+            }
+            if pulse {
+                self.glitches.push(Glitch {
+                    time,
+                    trace: naming::trace_label(index, trace),
+                    drivers: drivers(trace, sockets),
+                    kind: GlitchKind::ShortPulse {
+                        state: pulse_state,
+                        duration,
+                    },
+                });
+            }
+        }
+    }
+
+    pub(crate) fn begin_settle(&mut self) {
+        self.settling = true;
+        for record in &mut self.records {
+            record.transitions = 0;
+        }
+    }
+
+    /// Report the traces that changed more than once since `begin_settle`
+    pub(crate) fn end_settle(
+        &mut self,
+        time: Duration,
+        traces: &[Rc<RefCell<Trace>>],
+        sockets: &[Rc<RefCell<Socket>>],
+    ) {
+        self.settling = false;
+        for (index, (record, trace)) in self.records.iter().zip(traces).enumerate() {
+            if record.transitions > 1 {
+                self.glitches.push(Glitch {
+                    time,
+                    trace: naming::trace_label(index, trace),
+                    drivers: drivers(trace, sockets),
+                    kind: GlitchKind::MultipleTransitions {
+                        count: record.transitions,
+                    },
+                });
+            }
+        }
+    }
+}
+
+fn drivers(trace: &Rc<RefCell<Trace>>, sockets: &[Rc<RefCell<Socket>>]) -> Vec<String> {
+    let mut drivers = vec![];
+    for pin in trace.borrow().get_pins() {
+        let pin = pin.borrow();
+        if pin.pin_type != PinType::Output {
+            continue;
+        }
+        let socket = sockets
+            .iter()
+            .find(|socket| socket.borrow().get_uuid() == pin.parent);
+        let designator = match socket {
+            Some(socket) => naming::socket_label(&socket.borrow()),
+            None => String::from("?"),
+        };
+        drivers.push(format!("{}.{}", designator, pin.number));
+    }
+    drivers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chip::ChipRegistry, Board};
+
+    /// `A & !A` with the inverter lagging a step behind: a static hazard on `Y`
+    fn hazard() -> Board {
+        let mut board = Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part U1 virt_ic::GateNot
+             part U2 virt_ic::GateAnd
+             net VCC PWR.1 U1.14 U2.14
+             net GND PWR.2 U1.7 U2.7
+             net A U1.1 U2.1
+             net NA U1.2 U2.2
+             net Y U2.3",
+            &ChipRegistry::new(),
+        )
+        .unwrap();
+        let a = board.get_trace_by_name("A").unwrap();
+        a.borrow_mut().force(State::Low);
+        board.run_during(Duration::from_millis(5), Duration::from_millis(1));
+        a.borrow_mut().force(State::High);
+        board
+    }
+
+    #[test]
+    fn short_pulses_are_reported_with_their_drivers() {
+        let mut board = hazard();
+        board.enable_glitch_detection(Duration::from_millis(2));
+        board.run_during(Duration::from_millis(5), Duration::from_millis(1));
+        assert_eq!(
+            board.get_glitches(),
+            [Glitch {
+                time: Duration::from_millis(7),
+                trace: String::from("Y"),
+                drivers: vec![String::from("U2.3")],
+                kind: GlitchKind::ShortPulse {
+                    state: State::High,
+                    duration: Duration::from_millis(1),
+                },
+            }]
+        );
+
+        // a pulse as long as the threshold isn't a glitch
+        let mut board = hazard();
+        board.enable_glitch_detection(Duration::from_millis(1));
+        board.run_during(Duration::from_millis(5), Duration::from_millis(1));
+        assert!(board.get_glitches().is_empty());
+    }
+
+    #[test]
+    fn transitions_while_settling_are_reported() {
+        let mut board = hazard();
+        board.enable_glitch_detection(Duration::ZERO);
+        assert!(board.settle(Duration::from_millis(1), 10).is_some());
+        assert_eq!(
+            board.get_glitches(),
+            [Glitch {
+                time: board.get_elapsed(),
+                trace: String::from("Y"),
+                drivers: vec![String::from("U2.3")],
+                kind: GlitchKind::MultipleTransitions { count: 2 },
+            }]
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Checkpoints of a board's simulation, to step it backward
+use super::{fault::InjectedFault, BoardSnapshot, State};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub(crate) struct Checkpoint {
+    pub time: Duration,
+    pub snapshot: BoardSnapshot,
+    /// Forced state of each trace
+    pub forced: Vec<Option<State>>,
+    /// Faults injected at the time of the checkpoint, as they were applied
+    pub faults: Vec<InjectedFault>,
+    /// Steps run since the checkpoint, as (step, count)
+    pub steps: Vec<(Duration, u32)>,
+}
+
+impl Checkpoint {
+    pub fn record(&mut self, step: Duration) {
+        match self.steps.last_mut() {
+            Some((last, count)) if *last == step && *count < u32::MAX => *count += 1,
+            _ => self.steps.push((step, 1)),
+        }
+    }
+}
+
+/// Checkpoints taken periodically while a board runs, see `Board::enable_history`
+///
+/// Stepping back restores the nearest checkpoint and runs the recorded steps again.
+/// Only the board is replayed: pin states set from outside the board (`set_pin_state`, button presses ...),
+/// traces forced and faults injected or cleared after the checkpoint are lost,
+/// and chips relying on randomness like the RAM power-on content may diverge.
+#[derive(Debug)]
+pub struct History {
+    interval: Duration,
+    capacity: usize,
+    pub(crate) checkpoints: VecDeque<Checkpoint>,
+}
+
+impl History {
+    pub(crate) fn new(interval: Duration, capacity: usize) -> Self {
+        Self {
+            interval,
+            capacity: capacity.max(1),
+            checkpoints: VecDeque::new(),
+        }
+    }
+
+    /// Get the simulated time between two checkpoints
+    pub fn get_interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Get the maximum number of checkpoints kept, the oldest are dropped first
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the simulated time of every checkpoint, from the oldest
+    pub fn get_checkpoint_times(&self) -> Vec<Duration> {
+        self.checkpoints.iter().map(|cp| cp.time).collect()
+    }
+
+    /// Get the earliest time the board can be rewound to
+    pub fn get_oldest_time(&self) -> Option<Duration> {
+        self.checkpoints.front().map(|cp| cp.time)
+    }
+
+    pub(crate) fn needs_checkpoint(&self, time: Duration) -> bool {
+        self.checkpoints
+            .back()
+            .is_none_or(|cp| time >= cp.time + self.interval)
+    }
+
+    pub(crate) fn push(&mut self, checkpoint: Checkpoint) {
+        self.checkpoints.push_back(checkpoint);
+        while self.checkpoints.len() > self.capacity {
+            self.checkpoints.pop_front();
+        }
+    }
+
+    pub(crate) fn record(&mut self, step: Duration) {
+        if let Some(checkpoint) = self.checkpoints.back_mut() {
+            checkpoint.record(step);
+        }
+    }
+}
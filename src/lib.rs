@@ -1,12 +1,36 @@
+pub mod activity;
 mod board;
 pub mod chip;
+pub mod compiled;
+mod dot;
+mod error;
+pub mod expression;
+pub mod fault;
+mod glitch;
+mod history;
+mod naming;
+pub mod netlist;
+pub mod network;
+pub mod profile;
 mod save;
+mod snapshot;
 mod socket;
+mod std_logic;
+pub mod synthesis;
+pub mod testbench;
 mod trace;
+pub mod truth_table;
+pub mod verilog;
 pub use board::Board;
 pub use chip::{Chip, ChipInfo, Pin, PinType};
+pub use error::{Error, Result};
+pub use glitch::{Glitch, GlitchKind};
+pub use history::History;
+pub use save::{SaveFormat, FORMAT_VERSION};
 use serde::{Deserialize, Serialize};
+pub use snapshot::BoardSnapshot;
 pub use socket::Socket;
+pub use std_logic::{LogicModel, StdLogic};
 pub use trace::Trace;
 
 /// Current's State
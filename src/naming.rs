@@ -0,0 +1,75 @@
+//! Names given to the traces and sockets of a board in reports and exports
+use super::{Board, Chip, Socket, Trace};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// Name a trace by its name, or its number if it has none
+pub(crate) fn trace_label(index: usize, trace: &Rc<RefCell<Trace>>) -> String {
+    trace
+        .borrow()
+        .get_name()
+        .map_or_else(|| format!("trace #{}", index + 1), String::from)
+}
+
+/// Name a socket by its designator, or the type of its chip if it has none
+pub(crate) fn socket_label(socket: &Socket) -> String {
+    socket
+        .get_name()
+        .map_or_else(|| String::from(socket.get_type()), String::from)
+}
+
+/// Give a name to every socket of the board
+///
+/// Named sockets keep their name, others are named `U1`, `U2`, ... according to their position.
+pub(crate) fn socket_names(board: &Board) -> Vec<String> {
+    let names: Vec<Option<String>> = board
+        .get_sockets()
+        .iter()
+        .map(|socket| socket.borrow().get_name().map(String::from))
+        .collect();
+    fill_names(names, "U")
+}
+
+/// Give a name to every trace of the board
+///
+/// Named traces keep their name, others are named `N1`, `N2`, ... according to their position.
+pub(crate) fn trace_names(board: &Board) -> Vec<String> {
+    let names: Vec<Option<String>> = board
+        .get_traces()
+        .iter()
+        .map(|trace| trace.borrow().get_name().map(String::from))
+        .collect();
+    fill_names(names, "N")
+}
+
+/// Make a name unique among the `taken` ones with a `_2`, `_3`, ... suffix, and take it
+pub(crate) fn unique_name(taken: &mut HashSet<String>, base: String) -> String {
+    let mut name = base.clone();
+    let mut suffix = 1;
+    while !taken.insert(name.clone()) {
+        suffix += 1;
+        name = format!("{base}_{suffix}");
+    }
+    name
+}
+
+fn fill_names(names: Vec<Option<String>>, prefix: &str) -> Vec<String> {
+    let mut taken: HashSet<String> = names.iter().flatten().cloned().collect();
+    names
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| {
+            name.unwrap_or_else(|| {
+                let mut name = format!("{prefix}{}", index + 1);
+                let mut suffix = 0;
+                while taken.contains(&name) {
+                    suffix += 1;
+                    name = format!("{prefix}{}_{suffix}", index + 1);
+                }
+                taken.insert(name.clone());
+                name
+            })
+        })
+        .collect()
+}
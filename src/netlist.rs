@@ -0,0 +1,310 @@
+//! Human-editable text netlist format
+//!
+//! A netlist lists the parts of a board by designator and type, then the nets
+//! that link their pins together. Pins are referenced as `designator.pin`.
+//! Everything after a `#` is a comment.
+//! ```text
+//! # an AND gate powered by a generator
+//! part PWR virt_ic::Generator
+//! part U1  virt_ic::GateAnd
+//!
+//! net VCC PWR.1 U1.14
+//! net GND PWR.2 U1.7
+//! net OUT U1.3 U1.4
+//! ```
+//! A net may be split over several lines, every line adds its pins to the net.
+use super::{chip::ChipRegistry, naming, Board, Chip, Socket};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+/// An error encountered while parsing a netlist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetlistError {
+    /// Line of the netlist where the error occured, starting at 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl NetlistError {
+    fn new(line: usize, message: String) -> Self {
+        Self { line, message }
+    }
+}
+
+impl fmt::Display for NetlistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for NetlistError {}
+
+struct PartDecl<'a> {
+    line: usize,
+    designator: &'a str,
+    chip_type: &'a str,
+}
+
+struct NetDecl<'a> {
+    line: usize,
+    name: &'a str,
+    pins: Vec<&'a str>,
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains('.') && !name.contains('#')
+}
+
+/// Parse a netlist and build the corresponding board
+///
+/// Chips are created with the given `ChipRegistry`, `ChipRegistry::new()` knows the built-in chips
+pub fn parse(input: &str, registry: &ChipRegistry) -> Result<Board, NetlistError> {
+    let mut parts: Vec<PartDecl> = vec![];
+    let mut nets: Vec<NetDecl> = vec![];
+
+    for (index, raw_line) in input.lines().enumerate() {
+        let line = index + 1;
+        let content = raw_line.split('#').next().unwrap_or("");
+        let mut tokens = content.split_whitespace();
+        match tokens.next() {
+            None => {}
+            Some("part") => {
+                let designator = tokens.next().ok_or_else(|| {
+                    NetlistError::new(line, String::from("missing designator after `part`"))
+                })?;
+                let chip_type = tokens.next().ok_or_else(|| {
+                    NetlistError::new(line, format!("missing chip type for part `{designator}`"))
+                })?;
+                if let Some(extra) = tokens.next() {
+                    return Err(NetlistError::new(
+                        line,
+                        format!("unexpected `{extra}` after chip type"),
+                    ));
+                }
+                if !is_valid_name(designator) {
+                    return Err(NetlistError::new(
+                        line,
+                        format!("invalid designator `{designator}`"),
+                    ));
+                }
+                if let Some(other) = parts.iter().find(|p| p.designator == designator) {
+                    return Err(NetlistError::new(
+                        line,
+                        format!(
+                            "part `{designator}` is already declared on line {}",
+                            other.line
+                        ),
+                    ));
+                }
+                parts.push(PartDecl {
+                    line,
+                    designator,
+                    chip_type,
+                });
+            }
+            Some("net") => {
+                let name = tokens.next().ok_or_else(|| {
+                    NetlistError::new(line, String::from("missing name after `net`"))
+                })?;
+                if !is_valid_name(name) {
+                    return Err(NetlistError::new(
+                        line,
+                        format!("invalid net name `{name}`"),
+                    ));
+                }
+                nets.push(NetDecl {
+                    line,
+                    name,
+                    pins: tokens.collect(),
+                });
+            }
+            Some(keyword) => {
+                return Err(NetlistError::new(
+                    line,
+                    format!("unknown keyword `{keyword}`, expected `part` or `net`"),
+                ));
+            }
+        }
+    }
+
+    let mut board = Board::new();
+    let mut sockets: HashMap<&str, Rc<RefCell<Socket>>> = HashMap::new();
+    for part in &parts {
+        let chip = registry.create(part.chip_type).ok_or_else(|| {
+            NetlistError::new(part.line, format!("unknown chip type `{}`", part.chip_type))
+        })?;
+        let socket = board.new_socket_with(chip);
+        socket.borrow_mut().set_name(part.designator);
+        sockets.insert(part.designator, socket);
+    }
+
+    let mut traces = HashMap::new();
+    for net in &nets {
+        let trace = traces
+            .entry(net.name)
+            .or_insert_with(|| {
+                let trace = board.new_trace();
+                trace.borrow_mut().set_name(net.name);
+                trace
+            })
+            .clone();
+        for endpoint in &net.pins {
+            let (designator, pin) = endpoint.rsplit_once('.').ok_or_else(|| {
+                NetlistError::new(
+                    net.line,
+                    format!("invalid pin `{endpoint}`, expected `part.pin`"),
+                )
+            })?;
+            let socket = sockets.get(designator).ok_or_else(|| {
+                NetlistError::new(net.line, format!("unknown part `{designator}`"))
+            })?;
+            let number: u8 = pin.parse().map_err(|_| {
+                NetlistError::new(
+                    net.line,
+                    format!("invalid pin number `{pin}` in `{endpoint}`"),
+                )
+            })?;
+            let pin = socket.borrow_mut().get_pin(number).map_err(|err| {
+                NetlistError::new(net.line, format!("invalid pin `{endpoint}`: {err}"))
+            })?;
+            trace.borrow_mut().connect(pin);
+        }
+    }
+    Ok(board)
+}
+
+/// Make names readable back by `parse`: whitespaces, `.` and `#` are replaced by `_`,
+/// and a name already taken gets a `_2`, `_3`, ... suffix
+fn escape_names(names: Vec<String>) -> Vec<String> {
+    let mut taken = HashSet::new();
+    names
+        .into_iter()
+        .map(|name| {
+            let mut base: String = name
+                .chars()
+                .map(|c| {
+                    if c.is_whitespace() || c == '.' || c == '#' {
+                        '_'
+                    } else {
+                        c
+                    }
+                })
+                .collect();
+            if base.is_empty() {
+                base.push('_');
+            }
+            naming::unique_name(&mut taken, base)
+        })
+        .collect()
+}
+
+/// Write the netlist of a board
+///
+/// Empty sockets are skipped, and so are the pins of chips that aren't plugged on the board.
+/// Names that `parse` can't read back are escaped, and duplicated names are made unique,
+/// so that every socket and trace keeps its own designator or net.
+pub fn write(board: &Board) -> String {
+    let mut output = String::new();
+    let mut designators: HashMap<u128, String> = HashMap::new();
+    let sockets = board.get_sockets();
+    for (socket, name) in sockets
+        .iter()
+        .zip(escape_names(naming::socket_names(board)))
+    {
+        let socket = socket.borrow();
+        if !socket.has_chip() {
+            continue;
+        }
+        output.push_str(&format!("part {name} {}\n", socket.get_type()));
+        designators.insert(socket.get_uuid(), name);
+    }
+    if !designators.is_empty() {
+        output.push('\n');
+    }
+    for (trace, name) in board
+        .get_traces()
+        .iter()
+        .zip(escape_names(naming::trace_names(board)))
+    {
+        output.push_str(&format!("net {name}"));
+        for pin in trace.borrow().get_pins() {
+            let pin = pin.borrow();
+            if let Some(designator) = designators.get(&pin.parent) {
+                output.push_str(&format!(" {designator}.{}", pin.number));
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip::gates::GateAnd;
+
+    #[test]
+    fn unreadable_names_round_trip() {
+        let mut board = Board::new();
+        let gate = board.new_socket_with(Box::new(GateAnd::new()));
+        gate.borrow_mut().set_name("U 1");
+        let names = ["A B", "X", "X", "#c", "a.b", ""];
+        for (pin, name) in (1..).zip(names) {
+            let trace = board.new_trace();
+            trace.borrow_mut().set_name(name);
+            trace
+                .borrow_mut()
+                .connect(gate.borrow_mut().get_pin(pin).unwrap());
+        }
+        let netlist = write(&board);
+        let parsed = parse(&netlist, &ChipRegistry::new()).unwrap();
+        assert_eq!(parsed.get_traces().len(), names.len());
+        for trace in parsed.get_traces() {
+            assert_eq!(trace.borrow().get_pins().len(), 1);
+        }
+        assert!(parsed.get_socket_by_name("U_1").is_some());
+        assert!(parsed.get_trace_by_name("X_2").is_some());
+        assert_eq!(write(&parsed), netlist);
+    }
+
+    fn error(input: &str) -> NetlistError {
+        match parse(input, &ChipRegistry::new()) {
+            Ok(_) => panic!("{:?} parsed", input),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn malformed_netlists_are_errors() {
+        let and = "part U1 virt_ic::GateAnd\n";
+        assert_eq!(error("# comment\nwire A U1.1").line, 2);
+        assert_eq!(error("part").line, 1);
+        assert_eq!(error("part U1").line, 1);
+        assert_eq!(error("part U1 virt_ic::GateAnd extra").line, 1);
+        assert_eq!(error("part U1.2 virt_ic::GateAnd").line, 1);
+        assert_eq!(error(&format!("{and}{and}")).line, 2);
+        assert_eq!(error("part U1 virt_ic::Unknown").line, 1);
+        assert_eq!(error(&format!("{and}net")).line, 2);
+        assert_eq!(error(&format!("{and}net A.B U1.1")).line, 2);
+        assert_eq!(error(&format!("{and}net A U1")).line, 2);
+        assert_eq!(error(&format!("{and}net A U2.1")).line, 2);
+        assert_eq!(error(&format!("{and}net A U1.x")).line, 2);
+        assert_eq!(error(&format!("{and}net A U1.300")).line, 2);
+        assert_eq!(error(&format!("{and}net A U1.15")).line, 2);
+        assert_eq!(error(&format!("{and}net A U1.0")).line, 2);
+    }
+
+    #[test]
+    fn nets_split_over_lines_are_merged() {
+        let board = parse(
+            "part U1 virt_ic::GateAnd # a gate\n\nnet A U1.1\nnet A U1.2 U1.4\n",
+            &ChipRegistry::new(),
+        )
+        .unwrap();
+        assert_eq!(board.get_traces().len(), 1);
+        let trace = board.get_trace_by_name("A").unwrap();
+        assert_eq!(trace.borrow().get_pins().len(), 3);
+    }
+}
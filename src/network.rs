@@ -0,0 +1,357 @@
+//! Gate-level networks packed into the built-in gate chips
+//!
+//! A `GateNetwork` is a list of single logic gates linked by named nets.
+//! Building it packs the gates into the bundled gate chips (4 per `GateAnd`, 6 per `GateNot` ...),
+//! powers every chip with a `Generator` and links the nets with traces named after them.
+use super::{
+    chip::{
+        gates::{
+            self, Gate3InputAnd, Gate3InputNand, Gate3InputNor, GateAnd, GateKind, GateNand,
+            GateNor, GateNot, GateOr,
+        },
+        generators::Generator,
+    },
+    Board, Chip, Error, Result, Trace,
+};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// A single gate of the network
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkGate {
+    pub kind: GateKind,
+    pub inputs: Vec<String>,
+    pub output: String,
+}
+
+struct NetworkChip {
+    name: String,
+    chip: Box<dyn Chip>,
+    connections: Vec<(u8, String)>,
+}
+
+/// A network of single gates and chips linked by named nets
+#[derive(Default)]
+pub struct GateNetwork {
+    gates: Vec<NetworkGate>,
+    chips: Vec<NetworkChip>,
+}
+
+impl GateNetwork {
+    /// Name of the net tied to VCC
+    pub const HIGH: &'static str = "1'b1";
+    /// Name of the net tied to GND
+    pub const LOW: &'static str = "1'b0";
+
+    pub fn new() -> Self {
+        Self {
+            gates: vec![],
+            chips: vec![],
+        }
+    }
+
+    /// Add a gate reading the `inputs` nets and driving the `output` net
+    ///
+    /// Gates can have any number of inputs, they are split into smaller gates when packed.
+    /// Fails if the gate has no input, or if a NOT gate has more than one.
+    pub fn add_gate(&mut self, kind: GateKind, inputs: &[&str], output: &str) -> Result<()> {
+        if inputs.is_empty() || (kind == GateKind::Not && inputs.len() > 1) {
+            return Err(Error::InvalidLogic(format!(
+                "a {kind:?} gate can't have {} inputs, found driving `{output}`",
+                inputs.len()
+            )));
+        }
+        self.gates.push(NetworkGate {
+            kind,
+            inputs: inputs.iter().map(|input| String::from(*input)).collect(),
+            output: String::from(output),
+        });
+        Ok(())
+    }
+
+    /// Add a chip to the network, with its pins linked to the given nets
+    pub fn add_chip(&mut self, name: &str, chip: Box<dyn Chip>, connections: Vec<(u8, String)>) {
+        self.chips.push(NetworkChip {
+            name: String::from(name),
+            chip,
+            connections,
+        });
+    }
+
+    pub fn get_gates(&self) -> &[NetworkGate] {
+        &self.gates
+    }
+
+    /// Count the gate chips needed to build the network
+    pub fn chip_count(&self) -> usize {
+        self.pack(&mut NetNames::new(self)).len()
+    }
+
+    /// Split the gates so that every one of them fits in a gate chip,
+    /// and group them by the chip type that will hold them
+    fn pack(&self, names: &mut NetNames) -> Vec<(&'static str, Vec<NetworkGate>)> {
+        let mut units = vec![];
+        for gate in &self.gates {
+            decompose(
+                gate.kind,
+                gate.inputs.clone(),
+                gate.output.clone(),
+                names,
+                &mut units,
+            );
+        }
+        let mut packages: Vec<(&'static str, Vec<NetworkGate>)> = vec![];
+        for unit in units {
+            let chip_type = chip_type_for(unit.kind, unit.inputs.len());
+            let capacity = gates::get_gates(chip_type).map_or(0, <[_]>::len);
+            match packages
+                .iter_mut()
+                .find(|(t, gates)| *t == chip_type && gates.len() < capacity)
+            {
+                Some((_, gates)) => gates.push(unit),
+                None => packages.push((chip_type, vec![unit])),
+            }
+        }
+        packages
+    }
+
+    /// Build a board from the network
+    ///
+    /// Gate chips are named `U1`, `U2`, ... and the generator `PWR`,
+    /// unless these names are already used by the chips of the network.
+    /// Its power traces are named `VCC` and `GND` under the same condition.
+    pub fn build_board(self) -> Board {
+        let mut names = NetNames::new(&self);
+        let packages = self.pack(&mut names);
+
+        let mut designators: HashSet<String> =
+            self.chips.iter().map(|chip| chip.name.clone()).collect();
+        let mut next_designator = |prefix: &str, numbered: bool| {
+            let mut index = 1;
+            let mut name = if numbered {
+                format!("{prefix}{index}")
+            } else {
+                String::from(prefix)
+            };
+            while designators.contains(&name) {
+                index += 1;
+                name = format!("{prefix}{index}");
+            }
+            designators.insert(name.clone());
+            name
+        };
+
+        let mut board = Board::new();
+        let mut traces: HashMap<String, Rc<RefCell<Trace>>> = HashMap::new();
+        let vcc_name = names.unique("VCC");
+        let gnd_name = names.unique("GND");
+        let generator = board.new_socket_with(Box::new(Generator::new()));
+        generator
+            .borrow_mut()
+            .set_name(&next_designator("PWR", false));
+        let vcc = board.new_trace();
+        vcc.borrow_mut().set_name(&vcc_name);
+        let gnd = board.new_trace();
+        gnd.borrow_mut().set_name(&gnd_name);
+        if let Ok(pin) = generator.borrow_mut().get_pin(Generator::VCC) {
+            vcc.borrow_mut().connect(pin);
+        }
+        if let Ok(pin) = generator.borrow_mut().get_pin(Generator::GND) {
+            gnd.borrow_mut().connect(pin);
+        }
+        traces.insert(String::from(Self::HIGH), vcc.clone());
+        traces.insert(String::from(Self::LOW), gnd.clone());
+
+        let mut get_trace = |board: &mut Board, net: &str| {
+            traces
+                .entry(String::from(net))
+                .or_insert_with(|| {
+                    let trace = board.new_trace();
+                    trace.borrow_mut().set_name(net);
+                    trace
+                })
+                .clone()
+        };
+
+        for (chip_type, units) in packages {
+            let layout = gates::get_gates(chip_type).unwrap_or(&[]);
+            let chip = match new_gate_chip(chip_type) {
+                Some(chip) => chip,
+                None => continue,
+            };
+            let socket = board.new_socket_with(chip);
+            socket.borrow_mut().set_name(&next_designator("U", true));
+            // every built-in gate chip is powered by pins 14 and 7
+            if let Ok(pin) = socket.borrow_mut().get_pin(14) {
+                vcc.borrow_mut().connect(pin);
+            }
+            if let Ok(pin) = socket.borrow_mut().get_pin(7) {
+                gnd.borrow_mut().connect(pin);
+            }
+            for (unit, gate) in units.iter().zip(layout) {
+                for (net, pin) in unit.inputs.iter().zip(gate.inputs) {
+                    if let Ok(pin) = socket.borrow_mut().get_pin(*pin) {
+                        get_trace(&mut board, net).borrow_mut().connect(pin);
+                    }
+                }
+                if let Ok(pin) = socket.borrow_mut().get_pin(gate.output) {
+                    get_trace(&mut board, &unit.output)
+                        .borrow_mut()
+                        .connect(pin);
+                }
+            }
+        }
+
+        for chip in self.chips {
+            let socket = board.new_socket_with(chip.chip);
+            socket.borrow_mut().set_name(&chip.name);
+            for (number, net) in &chip.connections {
+                if let Ok(pin) = socket.borrow_mut().get_pin(*number) {
+                    get_trace(&mut board, net).borrow_mut().connect(pin);
+                }
+            }
+        }
+        board
+    }
+}
+
+/// Keeps track of the net names to create new unique ones
+struct NetNames {
+    used: HashSet<String>,
+    next: usize,
+}
+
+impl NetNames {
+    fn new(network: &GateNetwork) -> Self {
+        let mut used = HashSet::new();
+        for gate in &network.gates {
+            used.extend(gate.inputs.iter().cloned());
+            used.insert(gate.output.clone());
+        }
+        for chip in &network.chips {
+            used.extend(chip.connections.iter().map(|(_, net)| net.clone()));
+        }
+        Self { used, next: 1 }
+    }
+
+    fn unique(&mut self, name: &str) -> String {
+        let mut unique = String::from(name);
+        let mut index = 1;
+        while self.used.contains(&unique) {
+            unique = format!("{name}_{index}");
+            index += 1;
+        }
+        self.used.insert(unique.clone());
+        unique
+    }
+
+    fn intermediate(&mut self) -> String {
+        loop {
+            let name = format!("_n{}", self.next);
+            self.next += 1;
+            if self.used.insert(name.clone()) {
+                return name;
+            }
+        }
+    }
+}
+
+/// Get the chip type that holds a gate of the given kind and number of inputs
+fn chip_type_for(kind: GateKind, inputs: usize) -> &'static str {
+    match (kind, inputs) {
+        (GateKind::And, 3) => Gate3InputAnd::TYPE,
+        (GateKind::And, _) => GateAnd::TYPE,
+        (GateKind::Or, _) => GateOr::TYPE,
+        (GateKind::Not, _) => GateNot::TYPE,
+        (GateKind::Nand, 3) => Gate3InputNand::TYPE,
+        (GateKind::Nand, _) => GateNand::TYPE,
+        (GateKind::Nor, 3) => Gate3InputNor::TYPE,
+        (GateKind::Nor, _) => GateNor::TYPE,
+    }
+}
+
+fn new_gate_chip(chip_type: &str) -> Option<Box<dyn Chip>> {
+    match chip_type {
+        GateAnd::TYPE => Some(Box::new(GateAnd::new())),
+        GateOr::TYPE => Some(Box::new(GateOr::new())),
+        GateNot::TYPE => Some(Box::new(GateNot::new())),
+        GateNand::TYPE => Some(Box::new(GateNand::new())),
+        GateNor::TYPE => Some(Box::new(GateNor::new())),
+        Gate3InputAnd::TYPE => Some(Box::new(Gate3InputAnd::new())),
+        Gate3InputNand::TYPE => Some(Box::new(Gate3InputNand::new())),
+        Gate3InputNor::TYPE => Some(Box::new(Gate3InputNor::new())),
+        _ => None,
+    }
+}
+
+/// Get the largest number of inputs of a gate chip for a kind of gate
+fn max_inputs(kind: GateKind) -> usize {
+    match kind {
+        GateKind::Not => 1,
+        GateKind::Or => 2,
+        GateKind::And | GateKind::Nand | GateKind::Nor => 3,
+    }
+}
+
+/// Split a gate into gates that fit in the gate chips
+fn decompose(
+    kind: GateKind,
+    inputs: Vec<String>,
+    output: String,
+    names: &mut NetNames,
+    units: &mut Vec<NetworkGate>,
+) {
+    let max = max_inputs(kind);
+    if kind == GateKind::Not || (inputs.len() >= 2 && inputs.len() <= max) {
+        units.push(NetworkGate {
+            kind,
+            inputs,
+            output,
+        });
+        return;
+    }
+    match (kind, inputs.len()) {
+        (_, 0) => unreachable!("gates without input are rejected by `add_gate`"),
+        (GateKind::And, 1) | (GateKind::Or, 1) => {
+            // a single input gate is a buffer: two NOT gates
+            let inverted = names.intermediate();
+            decompose(GateKind::Not, inputs, inverted.clone(), names, units);
+            decompose(GateKind::Not, vec![inverted], output, names, units);
+        }
+        (_, 1) => decompose(GateKind::Not, inputs, output, names, units),
+        _ => {
+            // too many inputs: reduce them by groups with the non inverting gate
+            let base = match kind {
+                GateKind::Nand => GateKind::And,
+                GateKind::Nor => GateKind::Or,
+                _ => kind,
+            };
+            let group = max_inputs(base);
+            let mut reduced = vec![];
+            for chunk in inputs.chunks(group) {
+                if chunk.len() == 1 {
+                    reduced.push(chunk[0].clone());
+                } else {
+                    let net = names.intermediate();
+                    decompose(base, chunk.to_vec(), net.clone(), names, units);
+                    reduced.push(net);
+                }
+            }
+            decompose(kind, reduced, output, names, units);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gates_without_inputs_are_rejected() {
+        let mut network = GateNetwork::new();
+        assert!(network.add_gate(GateKind::And, &[], "Y").is_err());
+        assert!(network.add_gate(GateKind::Not, &["A", "B"], "Y").is_err());
+        assert!(network.add_gate(GateKind::Or, &["A"], "Y").is_ok());
+    }
+}
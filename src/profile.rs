@@ -0,0 +1,294 @@
+//! Execution profiling
+//!
+//! Once enabled with `Board::enable_profiling`, the board measures the wall-clock time spent
+//! in the `Chip::run` of every socket and the `Trace::communicate` of every trace.
+//! `Board::profile_report` sorts them from the slowest to the fastest:
+//! ```
+//! use std::time::Duration;
+//! use virt_ic::{chip::ChipRegistry, Board};
+//!
+//! let mut board = Board::from_netlist(
+//!     "part PWR virt_ic::Generator
+//!      part U1 virt_ic::GateNand
+//!      net VCC PWR.1 U1.14
+//!      net GND PWR.2 U1.7",
+//!     &ChipRegistry::new(),
+//! )
+//! .unwrap();
+//! board.enable_profiling();
+//! board.run_during(Duration::from_millis(100), Duration::from_millis(1));
+//! println!("{}", board.profile_report().unwrap());
+//! ```
+use super::{naming, Chip, Socket, Trace};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Call count and time spent
+#[derive(Debug, Clone, Copy, Default)]
+struct Timing {
+    calls: u64,
+    total: Duration,
+}
+
+impl Timing {
+    fn measure<T>(&mut self, function: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = function();
+        self.total += start.elapsed();
+        self.calls += 1;
+        result
+    }
+}
+
+/// Measures the time spent in the sockets and traces of a board
+#[derive(Debug, Default)]
+pub(crate) struct Profiler {
+    sockets: Vec<Timing>,
+    traces: Vec<Timing>,
+}
+
+impl Profiler {
+    pub(crate) fn communicate(&mut self, traces: &[Rc<RefCell<Trace>>]) {
+        self.traces.resize_with(traces.len(), Timing::default);
+        for (trace, timing) in traces.iter().zip(&mut self.traces) {
+            timing.measure(|| trace.borrow_mut().communicate());
+        }
+    }
+
+    pub(crate) fn run(&mut self, sockets: &[Rc<RefCell<Socket>>], time_elapsed: Duration) {
+        self.sockets.resize_with(sockets.len(), Timing::default);
+        for (socket, timing) in sockets.iter().zip(&mut self.sockets) {
+            timing.measure(|| socket.borrow_mut().run(time_elapsed));
+        }
+    }
+
+    pub(crate) fn report(
+        &self,
+        traces: &[Rc<RefCell<Trace>>],
+        sockets: &[Rc<RefCell<Socket>>],
+    ) -> ProfileReport {
+        let mut entries = vec![];
+        for (socket, timing) in sockets.iter().zip(&self.sockets) {
+            let socket = socket.borrow();
+            entries.push(ProfileEntry {
+                target: ProfileTarget::Socket {
+                    socket: naming::socket_label(&socket),
+                    chip_type: String::from(socket.get_type()),
+                },
+                calls: timing.calls,
+                total: timing.total,
+            });
+        }
+        for (index, (trace, timing)) in traces.iter().zip(&self.traces).enumerate() {
+            entries.push(ProfileEntry {
+                target: ProfileTarget::Trace(naming::trace_label(index, trace)),
+                calls: timing.calls,
+                total: timing.total,
+            });
+        }
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.total));
+        let total = entries.iter().map(|entry| entry.total).sum();
+        ProfileReport { entries, total }
+    }
+}
+
+/// What the time was spent in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileTarget {
+    /// The `Chip::run` of a socket, named by its designator or chip type
+    Socket { socket: String, chip_type: String },
+    /// The `Trace::communicate` of a trace, named by its name or number
+    Trace(String),
+}
+
+impl fmt::Display for ProfileTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Socket { socket, chip_type } if socket != chip_type => {
+                write!(f, "{socket} ({chip_type})")
+            }
+            Self::Socket { socket, .. } => write!(f, "{socket}"),
+            Self::Trace(trace) => write!(f, "trace {trace}"),
+        }
+    }
+}
+
+/// The time spent in a socket or a trace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileEntry {
+    pub target: ProfileTarget,
+    pub calls: u64,
+    pub total: Duration,
+}
+
+impl ProfileEntry {
+    /// Get the mean time of a call
+    pub fn get_mean(&self) -> Duration {
+        if self.calls == 0 {
+            return Duration::new(0, 0);
+        }
+        Duration::from_secs_f64(self.total.as_secs_f64() / self.calls as f64)
+    }
+}
+
+/// The time spent in every socket and trace, from the slowest to the fastest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileReport {
+    pub entries: Vec<ProfileEntry>,
+    /// Time spent in all of them
+    pub total: Duration,
+}
+
+impl ProfileReport {
+    /// Sum the time spent in the sockets by chip type, from the slowest to the fastest
+    pub fn by_chip_type(&self) -> Vec<(String, Duration)> {
+        let mut types: BTreeMap<&str, Duration> = BTreeMap::new();
+        for entry in &self.entries {
+            if let ProfileTarget::Socket { chip_type, .. } = &entry.target {
+                *types.entry(chip_type).or_default() += entry.total;
+            }
+        }
+        let mut types: Vec<(String, Duration)> = types
+            .into_iter()
+            .map(|(chip_type, total)| (String::from(chip_type), total))
+            .collect();
+        types.sort_by_key(|(_, total)| std::cmp::Reverse(*total));
+        types
+    }
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total.as_secs_f64();
+        for entry in &self.entries {
+            let share = if total > 0.0 {
+                entry.total.as_secs_f64() / total * 100.0
+            } else {
+                0.0
+            };
+            writeln!(
+                f,
+                "{:>6.2}% {:>12?} {:>10} calls {:>10?}/call  {}",
+                share,
+                entry.total,
+                entry.calls,
+                entry.get_mean(),
+                entry.target
+            )?;
+        }
+        write!(f, "total: {:?}", self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chip::{gates::GateNot, generators::Generator, ChipInfo, ChipRegistry},
+        Board, Pin,
+    };
+
+    /// A chip slower than any other
+    struct Sleeper;
+
+    impl Sleeper {
+        const TYPE: &'static str = "test::Sleeper";
+    }
+
+    impl Chip for Sleeper {
+        fn get_uuid(&self) -> u128 {
+            1
+        }
+        fn get_type(&self) -> &str {
+            Self::TYPE
+        }
+        fn run(&mut self, _: Duration) {
+            std::thread::sleep(Duration::from_micros(200));
+        }
+        fn get_pin_qty(&self) -> u8 {
+            0
+        }
+        fn _get_pin(&mut self, _: u8) -> Rc<RefCell<Pin>> {
+            unreachable!("the sleeper has no pin")
+        }
+        fn get_info(&self) -> ChipInfo {
+            ChipInfo {
+                name: "Sleeper",
+                description: "",
+                data: String::new(),
+            }
+        }
+    }
+
+    #[test]
+    fn calls_are_counted_and_sorted() {
+        let mut board = Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part U1 virt_ic::GateNot
+             net VCC PWR.1 U1.14
+             net GND PWR.2 U1.7",
+            &ChipRegistry::new(),
+        )
+        .unwrap();
+        board
+            .new_socket_with(Box::new(Sleeper))
+            .borrow_mut()
+            .set_name("SLOW");
+        assert!(board.profile_report().is_none());
+        board.enable_profiling();
+        board.run_during(Duration::from_millis(10), Duration::from_millis(1));
+
+        let report = board.profile_report().unwrap();
+        let mut targets: Vec<String> = report
+            .entries
+            .iter()
+            .map(|entry| {
+                assert_eq!(entry.calls, 10, "{}", entry.target);
+                entry.target.to_string()
+            })
+            .collect();
+        targets.sort();
+        assert_eq!(
+            targets,
+            [
+                "PWR (virt_ic::Generator)",
+                "SLOW (test::Sleeper)",
+                "U1 (virt_ic::GateNot)",
+                "trace GND",
+                "trace VCC"
+            ]
+        );
+        assert!(report
+            .entries
+            .windows(2)
+            .all(|pair| pair[0].total >= pair[1].total));
+        assert_eq!(
+            report.entries[0].target,
+            ProfileTarget::Socket {
+                socket: String::from("SLOW"),
+                chip_type: String::from(Sleeper::TYPE),
+            }
+        );
+        assert!(report.entries[0].get_mean() >= Duration::from_micros(200));
+        assert_eq!(
+            report.total,
+            report.entries.iter().map(|entry| entry.total).sum()
+        );
+
+        let types = report.by_chip_type();
+        assert_eq!(types.len(), 3);
+        assert_eq!(types[0].0, Sleeper::TYPE);
+        assert!(types.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        for chip_type in [GateNot::TYPE, Generator::TYPE] {
+            assert!(types.iter().any(|(name, _)| name == chip_type));
+        }
+
+        board.reset_profiling();
+        let report = board.profile_report().unwrap();
+        assert!(report.entries.is_empty());
+        assert_eq!(report.total, Duration::ZERO);
+    }
+}
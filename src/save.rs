@@ -1,22 +1,57 @@
-use super::{Board, Chip, Pin, Socket};
+use super::{
+    chip::{ChipData, ChipRegistry},
+    Board, Chip, Error, Pin, Result, Socket,
+};
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{Read, Write};
 use std::rc::Rc;
 
+/// Version of the save format written by this version of the crate
+///
+/// Files without a version were written before versioning and are read as version 0.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// The serde formats boards can be saved to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveFormat {
+    /// Rusty Object Notation, the default format of `Board::save`
+    Ron,
+    Json,
+    /// CBOR, a compact binary format
+    Cbor,
+}
+
+impl SaveFormat {
+    /// Returns true if the format is human-readable text
+    pub fn is_text(&self) -> bool {
+        !matches!(self, Self::Cbor)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SavedChip {
     pub uuid: u128,
     pub chip_type: String,
-    pub chip_data: Vec<String>,
+    /// Version of the layout of `chip_data`, see `Chip::data_version`
+    #[serde(default)]
+    pub data_version: u32,
+    pub chip_data: ChipData,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SavedSocket {
+    #[serde(default)]
+    pub name: Option<String>,
     pub chip: Option<SavedChip>,
 }
 impl SavedSocket {
     pub fn new() -> Self {
-        Self { chip: None }
+        Self {
+            name: None,
+            chip: None,
+        }
     }
     pub fn set_chip(&mut self, chip: SavedChip) {
         self.chip = Some(chip);
@@ -25,11 +60,16 @@ impl SavedSocket {
 
 #[derive(Default, Debug, Serialize, Deserialize)]
 pub struct SavedTrace {
+    #[serde(default)]
+    pub name: Option<String>,
     pub pins: Vec<Pin>,
 }
 impl SavedTrace {
     pub fn new() -> Self {
-        Self { pins: vec![] }
+        Self {
+            name: None,
+            pins: vec![],
+        }
     }
 
     pub fn add_trace(&mut self, pin: Pin) {
@@ -39,6 +79,8 @@ impl SavedTrace {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SavedBoard {
+    #[serde(default)]
+    version: u32,
     sockets: Vec<SavedSocket>,
     traces: Vec<SavedTrace>,
 }
@@ -46,10 +88,25 @@ pub struct SavedBoard {
 impl SavedBoard {
     pub fn new() -> Self {
         Self {
+            version: FORMAT_VERSION,
             sockets: vec![],
             traces: vec![],
         }
     }
+
+    /// Upgrade a board saved by an older version of the crate to the current format
+    pub fn migrate(&mut self) -> Result<()> {
+        if self.version > FORMAT_VERSION {
+            return Err(Error::UnsupportedVersion {
+                version: self.version,
+                supported: FORMAT_VERSION,
+            });
+        }
+        // 0 -> 1: socket and trace names were added, they default to None
+        // 1 -> 2: chip data became structured, the chips migrate their own data from `data_version` 0
+        self.version = FORMAT_VERSION;
+        Ok(())
+    }
     pub fn add_trace(&mut self, trace: SavedTrace) {
         self.traces.push(trace);
     }
@@ -57,23 +114,50 @@ impl SavedBoard {
         self.sockets.push(socket);
     }
 
-    pub fn build_board(&self, chip_factory: &dyn Fn(&str) -> Option<Box<dyn Chip>>) -> Board {
+    pub fn write<W: Write>(&self, writer: W, format: SaveFormat) -> Result<()> {
+        match format {
+            SaveFormat::Ron => ron::ser::to_writer(writer, self)?,
+            SaveFormat::Json => serde_json::to_writer(writer, self)?,
+            SaveFormat::Cbor => ciborium::ser::into_writer(self, writer)
+                .map_err(|err| Error::Cbor(err.to_string()))?,
+        }
+        Ok(())
+    }
+
+    pub fn read<R: Read>(reader: R, format: SaveFormat) -> Result<Self> {
+        Ok(match format {
+            SaveFormat::Ron => ron::de::from_reader(reader)?,
+            SaveFormat::Json => serde_json::from_reader(reader)?,
+            SaveFormat::Cbor => {
+                ciborium::de::from_reader(reader).map_err(|err| Error::Cbor(err.to_string()))?
+            }
+        })
+    }
+
+    pub fn build_board(&self, registry: &ChipRegistry) -> Result<Board> {
         let mut board = Board::new();
         let mut loaded_chips: Vec<(u128, Rc<RefCell<Socket>>)> = vec![];
 
         for s_socket in &self.sockets {
             let socket = board.new_socket();
+            if let Some(name) = &s_socket.name {
+                socket.borrow_mut().set_name(name);
+            }
             if let Some(s_chip) = &s_socket.chip {
-                if let Some(chip) = chip_factory(&s_chip.chip_type) {
-                    socket.borrow_mut().plug(chip);
-                    socket.borrow_mut().load(s_chip);
-                    loaded_chips.push((s_chip.uuid, socket.clone()));
-                }
+                let chip = registry
+                    .create(&s_chip.chip_type)
+                    .ok_or_else(|| Error::UnknownChipType(s_chip.chip_type.clone()))?;
+                socket.borrow_mut().plug(chip);
+                socket.borrow_mut().load(s_chip)?;
+                loaded_chips.push((s_chip.uuid, socket.clone()));
             }
         }
 
         for s_trace in &self.traces {
             let trace = board.new_trace();
+            if let Some(name) = &s_trace.name {
+                trace.borrow_mut().set_name(name);
+            }
             for s_pin in &s_trace.pins {
                 for l_chip in &loaded_chips {
                     if s_pin.parent == l_chip.0 {
@@ -86,6 +170,71 @@ impl SavedBoard {
                 }
             }
         }
-        board
+        Ok(board)
+    }
+
+    /// Replace the uuids of the chips by their socket position starting at 1, and drop the pins of other chips
+    /// Sub-boards save their inner board this way, so that it fits in a `ChipData`.
+    pub(crate) fn renumber(&mut self) {
+        let mut ids: HashMap<u128, u128> = HashMap::new();
+        for (index, socket) in self.sockets.iter_mut().enumerate() {
+            if let Some(chip) = socket.chip.as_mut() {
+                ids.insert(chip.uuid, index as u128 + 1);
+                chip.uuid = index as u128 + 1;
+            }
+        }
+        for trace in &mut self.traces {
+            trace.pins.retain_mut(|pin| match ids.get(&pin.parent) {
+                Some(id) => {
+                    pin.parent = *id;
+                    true
+                }
+                None => false,
+            });
+        }
+    }
+
+    /// Load the state of the chips and pins into a board holding the same chips in the same sockets
+    pub(crate) fn restore_into(&self, board: &Board, chip_type: &str) -> Result<()> {
+        let sockets = board.get_sockets();
+        if sockets.len() != self.sockets.len() {
+            return Err(Error::invalid_chip_data(
+                chip_type,
+                format!(
+                    "expected {} sockets, found {}",
+                    sockets.len(),
+                    self.sockets.len()
+                ),
+            ));
+        }
+        let mut loaded_chips: HashMap<u128, Rc<RefCell<Socket>>> = HashMap::new();
+        for (index, (socket, s_socket)) in sockets.iter().zip(&self.sockets).enumerate() {
+            if let Some(s_chip) = &s_socket.chip {
+                if socket.borrow().get_type() != s_chip.chip_type {
+                    return Err(Error::invalid_chip_data(
+                        chip_type,
+                        format!(
+                            "socket {} holds a `{}`, found `{}`",
+                            index + 1,
+                            socket.borrow().get_type(),
+                            s_chip.chip_type
+                        ),
+                    ));
+                }
+                socket.borrow_mut().load(s_chip)?;
+                loaded_chips.insert(s_chip.uuid, socket.clone());
+            }
+        }
+        for s_trace in &self.traces {
+            for s_pin in &s_trace.pins {
+                if let Some(socket) = loaded_chips.get(&s_pin.parent) {
+                    if let Ok(pin) = socket.borrow_mut().get_pin(s_pin.number) {
+                        pin.borrow_mut().state = s_pin.state.clone();
+                        pin.borrow_mut().pin_type = s_pin.pin_type.clone();
+                    }
+                }
+            }
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,26 @@
+//! In-memory snapshots of a board's state
+use super::Pin;
+use std::any::Any;
+
+pub(crate) struct SocketSnapshot {
+    pub uuid: u128,
+    /// Copies of the pins, with their type, state, level and voltage
+    pub pins: Vec<Pin>,
+    pub chip: Box<dyn Any>,
+}
+
+/// The state of every pin and chip of a board, captured by `Board::snapshot`
+///
+/// A snapshot can only be restored on the board it was taken from, as long as its chips haven't changed.
+/// It can be restored any number of times.
+pub struct BoardSnapshot {
+    pub(crate) sockets: Vec<Option<SocketSnapshot>>,
+}
+
+impl std::fmt::Debug for BoardSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BoardSnapshot")
+            .field("sockets", &self.sockets.len())
+            .finish()
+    }
+}
@@ -1,4 +1,5 @@
-use super::{Chip, ChipInfo, Pin, PinType, State};
+use super::{chip::ChipData, Chip, ChipInfo, Error, LogicModel, Pin, PinType, Result, State};
+use std::any::Any;
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -6,16 +7,33 @@ use std::rc::Rc;
 #[derive(Default, Debug)]
 pub struct Socket {
     chip: Option<Box<dyn Chip>>,
+    name: Option<String>,
 }
 
 impl Socket {
     #[must_use]
     pub fn new() -> Self {
-        Self { chip: None }
+        Self {
+            chip: None,
+            name: None,
+        }
     }
 
     pub fn with(chip: Box<dyn Chip>) -> Self {
-        Self { chip: Some(chip) }
+        Self {
+            chip: Some(chip),
+            name: None,
+        }
+    }
+
+    /// Get the designator of the socket (e.g. `U1`), if it has one
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Give a designator to the socket, used by netlists and exports
+    pub fn set_name(&mut self, name: &str) {
+        self.name = Some(String::from(name));
     }
 
     pub fn plug(&mut self, chip: Box<dyn Chip>) {
@@ -30,6 +48,21 @@ impl Socket {
         &mut self.chip
     }
 
+    /// Get the plugged chip as its struct, if it's a `C`
+    /// ```
+    /// use virt_ic::{chip::buttons::Button, Socket};
+    ///
+    /// let mut socket = Socket::with(Box::new(Button::new()));
+    /// if let Some(button) = socket.get_chip_as::<Button>() {
+    ///     button.press();
+    /// }
+    /// ```
+    pub fn get_chip_as<C: Chip + 'static>(&mut self) -> Option<&mut C> {
+        self.chip
+            .as_mut()
+            .and_then(|chip| (**chip).as_any_mut().downcast_mut())
+    }
+
     pub fn get_pin_type(&mut self, pin: u8) -> PinType {
         self.chip.as_mut().map_or(PinType::Undefined, |chip| {
             chip.get_pin(pin)
@@ -62,10 +95,10 @@ impl Chip for Socket {
         panic!("_get_pin is not intended to be called for a Socket !");
     }
 
-    fn get_pin(&mut self, pin: u8) -> Result<Rc<RefCell<Pin>>, &str> {
+    fn get_pin(&mut self, pin: u8) -> Result<Rc<RefCell<Pin>>> {
         self.chip
             .as_mut()
-            .map_or(Err("No chip connected"), |chip| chip.get_pin(pin))
+            .map_or(Err(Error::NoChip), |chip| chip.get_pin(pin))
     }
 
     fn get_pin_state(&mut self, pin: u8) -> State {
@@ -99,14 +132,37 @@ impl Chip for Socket {
             chip.run(elapsed_time);
         }
     }
-    fn save_data(&self) -> Vec<String> {
+    fn set_logic_model(&mut self, model: LogicModel) {
+        if let Some(chip) = self.chip.as_mut() {
+            chip.set_logic_model(model);
+        }
+    }
+    fn save_data(&self) -> Result<ChipData> {
         self.chip
             .as_ref()
-            .map_or_else(std::vec::Vec::new, |chip| chip.save_data())
+            .map_or(Ok(ChipData::Null), |chip| chip.save_data())
     }
-    fn load_data(&mut self, s_chip: &[String]) {
-        if let Some(chip) = self.chip.as_mut() {
-            chip.load_data(s_chip);
-        }
+    fn data_version(&self) -> u32 {
+        self.chip.as_ref().map_or(0, |chip| chip.data_version())
+    }
+    fn migrate_data(&self, from_version: u32, chip_data: ChipData) -> Option<ChipData> {
+        self.chip
+            .as_ref()
+            .and_then(|chip| chip.migrate_data(from_version, chip_data))
+    }
+    fn load_data(&mut self, s_chip: &ChipData) -> Result<()> {
+        self.chip
+            .as_mut()
+            .map_or(Err(Error::NoChip), |chip| chip.load_data(s_chip))
+    }
+    fn snapshot(&self) -> Box<dyn Any> {
+        self.chip
+            .as_ref()
+            .map_or_else(|| Box::new(()) as Box<dyn Any>, |chip| chip.snapshot())
+    }
+    fn restore(&mut self, snapshot: &dyn Any) -> Result<()> {
+        self.chip
+            .as_mut()
+            .map_or(Err(Error::NoChip), |chip| chip.restore(snapshot))
     }
 }
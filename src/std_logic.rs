@@ -0,0 +1,370 @@
+//! Nine-valued logic modelled on IEEE 1164 `std_logic`
+//!
+//! By default boards use the binary model: pins and traces are High, Low or Undefined,
+//! a trace is High as soon as one of its outputs is, and the built-in gates read anything that
+//! isn't High as Low.
+//! With `Board::set_logic_model(LogicModel::StdLogic)`, the pins also carry a `StdLogic` level:
+//! traces resolve the levels of their outputs with the IEEE 1164 resolution table,
+//! and the built-in gates compute their outputs with the IEEE 1164 tables, propagating unknowns.
+//! ```
+//! use std::time::Duration;
+//! use virt_ic::{chip::ChipRegistry, Board, LogicModel, State, StdLogic};
+//!
+//! let mut board = Board::from_netlist(
+//!     "part PWR virt_ic::Generator
+//!      part U1 virt_ic::GateNot
+//!      net VCC PWR.1 U1.14 U1.1
+//!      net GND PWR.2 U1.7 U1.3
+//!      net Y U1.2 U1.4",
+//!     &ChipRegistry::new(),
+//! )
+//! .unwrap();
+//! board.set_logic_model(LogicModel::StdLogic);
+//! board.run_during(Duration::from_millis(3), Duration::from_millis(1));
+//! // two inverters drive Y to '0' and '1' at once, the binary model would read it High
+//! let y = board.get_trace_by_name("Y").unwrap();
+//! assert_eq!(y.borrow().get_level(), StdLogic::Unknown);
+//! assert_eq!(y.borrow().get_state(), State::Undefined);
+//! ```
+//! In between, `LogicModel::XPropagation` keeps the three states of the binary model
+//! but makes the built-in gates propagate Undefined, so that floating inputs show up on their outputs.
+//!
+//! Chips that only know `State` keep working: the level of a pin follows its state
+//! as soon as such a chip changes it, High and Low being strong levels and Undefined high impedance.
+use super::State;
+use std::fmt;
+use std::ops::{BitAnd, BitOr, Not};
+
+/// A nine-valued logic level, like IEEE 1164 `std_ulogic`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StdLogic {
+    /// 'U'
+    #[default]
+    Uninitialized,
+    /// 'X', forcing unknown
+    Unknown,
+    /// '0', forcing 0
+    Low,
+    /// '1', forcing 1
+    High,
+    /// 'Z'
+    HighImpedance,
+    /// 'W'
+    WeakUnknown,
+    /// 'L', weak 0
+    WeakLow,
+    /// 'H', weak 1
+    WeakHigh,
+    /// '-'
+    DontCare,
+}
+
+/// The IEEE 1164 tables, indexed in the order of `StdLogic::ALL`
+const RESOLUTION: [&str; 9] = [
+    "UUUUUUUUU",
+    "UXXXXXXXX",
+    "UX0X0000X",
+    "UXX11111X",
+    "UX01ZWLHX",
+    "UX01WWWWX",
+    "UX01LWLWX",
+    "UX01HWWHX",
+    "UXXXXXXXX",
+];
+const AND: [&str; 9] = [
+    "UU0UUU0UU",
+    "UX0XXX0XX",
+    "000000000",
+    "UX01XX01X",
+    "UX0XXX0XX",
+    "UX0XXX0XX",
+    "000000000",
+    "UX01XX01X",
+    "UX0XXX0XX",
+];
+const OR: [&str; 9] = [
+    "UUU1UUU1U",
+    "UXX1XXX1X",
+    "UX01XX01X",
+    "111111111",
+    "UXX1XXX1X",
+    "UXX1XXX1X",
+    "UX01XX01X",
+    "111111111",
+    "UXX1XXX1X",
+];
+const NOT: &str = "UX10XX10X";
+
+impl StdLogic {
+    /// Every level, in the order of the IEEE 1164 tables
+    pub const ALL: [StdLogic; 9] = [
+        Self::Uninitialized,
+        Self::Unknown,
+        Self::Low,
+        Self::High,
+        Self::HighImpedance,
+        Self::WeakUnknown,
+        Self::WeakLow,
+        Self::WeakHigh,
+        Self::DontCare,
+    ];
+
+    /// Get a level from its IEEE 1164 character: `U X 0 1 Z W L H -`
+    pub fn from_char(character: char) -> Option<Self> {
+        match character.to_ascii_uppercase() {
+            'U' => Some(Self::Uninitialized),
+            'X' => Some(Self::Unknown),
+            '0' => Some(Self::Low),
+            '1' => Some(Self::High),
+            'Z' => Some(Self::HighImpedance),
+            'W' => Some(Self::WeakUnknown),
+            'L' => Some(Self::WeakLow),
+            'H' => Some(Self::WeakHigh),
+            '-' => Some(Self::DontCare),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Self::Uninitialized => 'U',
+            Self::Unknown => 'X',
+            Self::Low => '0',
+            Self::High => '1',
+            Self::HighImpedance => 'Z',
+            Self::WeakUnknown => 'W',
+            Self::WeakLow => 'L',
+            Self::WeakHigh => 'H',
+            Self::DontCare => '-',
+        }
+    }
+
+    /// Get the level two drivers give together on a trace
+    pub fn resolve(self, other: Self) -> Self {
+        lookup(&RESOLUTION, self, other)
+    }
+
+    /// Get the level of a trace driven by some outputs, high impedance without any
+    pub fn resolve_all<I: IntoIterator<Item = StdLogic>>(levels: I) -> Self {
+        levels
+            .into_iter()
+            .fold(Self::HighImpedance, |resolved, level| {
+                resolved.resolve(level)
+            })
+    }
+
+    /// Strip the strength of the level, like `To_X01`: weak levels become strong, the others unknown
+    pub fn to_x01(self) -> Self {
+        match self {
+            Self::Low | Self::WeakLow => Self::Low,
+            Self::High | Self::WeakHigh => Self::High,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+fn lookup(table: &[&str; 9], left: StdLogic, right: StdLogic) -> StdLogic {
+    let character = table[left as usize].as_bytes()[right as usize] as char;
+    // unwrap because the tables only hold valid characters
+    StdLogic::from_char(character).unwrap()
+}
+
+impl BitAnd for StdLogic {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        lookup(&AND, self, other)
+    }
+}
+
+impl BitOr for StdLogic {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        lookup(&OR, self, other)
+    }
+}
+
+impl Not for StdLogic {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        // unwrap because the table only holds valid characters
+        Self::from_char(NOT.as_bytes()[self as usize] as char).unwrap()
+    }
+}
+
+impl From<State> for StdLogic {
+    /// High and Low are forcing levels, Undefined doesn't drive anything
+    fn from(state: State) -> Self {
+        match state {
+            State::High => Self::High,
+            State::Low => Self::Low,
+            State::Undefined => Self::HighImpedance,
+        }
+    }
+}
+
+impl From<StdLogic> for State {
+    /// Weak levels are read as strong ones, the others as Undefined
+    fn from(level: StdLogic) -> Self {
+        match level.to_x01() {
+            StdLogic::High => Self::High,
+            StdLogic::Low => Self::Low,
+            _ => Self::Undefined,
+        }
+    }
+}
+
+impl fmt::Display for StdLogic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+/// The value set the traces and the built-in gates of a board work with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogicModel {
+    /// High, Low and Undefined: a trace is High if one of its outputs is, gates read Undefined as Low
+    #[default]
+    Binary,
+    /// High, Low and Undefined like `Binary`, but the built-in gates propagate Undefined:
+    /// an AND with a Low input is Low, an OR with a High input is High, an Undefined input gives Undefined otherwise
+    XPropagation,
+    /// The nine `StdLogic` levels, resolved and propagated with the IEEE 1164 tables
+    StdLogic,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use StdLogic::*;
+
+    fn level(character: char) -> StdLogic {
+        StdLogic::from_char(character).unwrap()
+    }
+
+    fn commutative(operation: fn(StdLogic, StdLogic) -> StdLogic) {
+        for left in StdLogic::ALL {
+            for right in StdLogic::ALL {
+                assert_eq!(
+                    operation(left, right),
+                    operation(right, left),
+                    "{} with {}",
+                    left,
+                    right
+                );
+            }
+        }
+    }
+
+    /// '-' has no meaning as an operand, it's read as 'X'
+    fn dont_care_is_unknown(operation: fn(StdLogic, StdLogic) -> StdLogic) {
+        for other in StdLogic::ALL {
+            assert_eq!(
+                operation(DontCare, other),
+                operation(Unknown, other),
+                "- with {}",
+                other
+            );
+        }
+    }
+
+    #[test]
+    fn resolution_is_commutative_and_associative() {
+        commutative(StdLogic::resolve);
+        for a in StdLogic::ALL {
+            for b in StdLogic::ALL {
+                for c in StdLogic::ALL {
+                    assert_eq!(
+                        a.resolve(b).resolve(c),
+                        a.resolve(b.resolve(c)),
+                        "{} {} {}",
+                        a,
+                        b,
+                        c
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn resolution_is_dominated_by_uninitialized() {
+        for other in StdLogic::ALL {
+            assert_eq!(Uninitialized.resolve(other), Uninitialized);
+        }
+    }
+
+    #[test]
+    fn high_impedance_doesnt_drive() {
+        for other in StdLogic::ALL {
+            let expected = if other == DontCare { Unknown } else { other };
+            assert_eq!(HighImpedance.resolve(other), expected);
+        }
+        assert_eq!(StdLogic::resolve_all([]), HighImpedance);
+    }
+
+    #[test]
+    fn dont_care_is_read_as_unknown() {
+        dont_care_is_unknown(StdLogic::resolve);
+        dont_care_is_unknown(|left, right| left & right);
+        dont_care_is_unknown(|left, right| left | right);
+        assert_eq!(!DontCare, !Unknown);
+    }
+
+    #[test]
+    fn gates_follow_boolean_logic_on_known_levels() {
+        commutative(|left, right| left & right);
+        commutative(|left, right| left | right);
+        let known = [Low, High, WeakLow, WeakHigh];
+        let value = |level: StdLogic| level.to_x01() == High;
+        for a in known {
+            assert_eq!(value(!a), !value(a));
+            for b in known {
+                assert_eq!((a & b).to_x01(), a & b, "{} & {} is weak", a, b);
+                assert_eq!(value(a & b), value(a) && value(b));
+                assert_eq!(value(a | b), value(a) || value(b));
+                // De Morgan
+                assert_eq!(!(a & b), !a | !b, "{} {}", a, b);
+                assert_eq!(!(a | b), !a & !b, "{} {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn controlling_inputs_hide_unknowns() {
+        for other in StdLogic::ALL {
+            assert_eq!(Low & other, Low);
+            assert_eq!(WeakLow & other, Low);
+            assert_eq!(High | other, High);
+            assert_eq!(WeakHigh | other, High);
+        }
+    }
+
+    #[test]
+    fn ieee_cells() {
+        assert_eq!(level('0').resolve(level('1')), level('X'));
+        assert_eq!(level('L').resolve(level('H')), level('W'));
+        assert_eq!(level('0').resolve(level('L')), level('0'));
+        assert_eq!(level('Z').resolve(level('H')), level('H'));
+        assert_eq!(level('W').resolve(level('L')), level('W'));
+        assert_eq!(level('0') & level('X'), level('0'));
+        assert_eq!(level('1') & level('Z'), level('X'));
+        assert_eq!(level('U') & level('1'), level('U'));
+        assert_eq!(level('1') | level('U'), level('1'));
+        assert_eq!(level('0') | level('W'), level('X'));
+        assert_eq!(level('L') | level('H'), level('1'));
+        assert_eq!(!level('H'), level('0'));
+        assert_eq!(!level('Z'), level('X'));
+        assert_eq!(!level('U'), level('U'));
+    }
+
+    #[test]
+    fn chars_round_trip() {
+        for level in StdLogic::ALL {
+            assert_eq!(StdLogic::from_char(level.to_char()), Some(level));
+        }
+        assert_eq!(StdLogic::from_char('?'), None);
+    }
+}
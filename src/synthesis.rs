@@ -0,0 +1,605 @@
+//! Synthesis of boolean expressions into gate chips
+//!
+//! A `Synthesizer` takes an expression per output and builds the gates computing them,
+//! packed into the built-in gate chips by a `GateNetwork`.
+//! Each output is minimized into a sum of products with the Quine-McCluskey method,
+//! the expression is kept as written when that takes fewer chips (XORs for instance).
+//! ```
+//! use virt_ic::synthesis::Synthesizer;
+//!
+//! let mut synthesizer = Synthesizer::new();
+//! synthesizer.add_equation("Y = !(A & B) | C").unwrap();
+//! synthesizer.add_equation("Z = A ^ C").unwrap();
+//! let board = synthesizer.build_board();
+//! // the inputs and outputs are the traces named after them
+//! assert!(board.get_trace_by_name("A").is_some());
+//! assert!(board.get_trace_by_name("Y").is_some());
+//! ```
+use super::{
+    chip::{gates::GateKind, subboard::SubBoard},
+    expression::{Expression, ExpressionError},
+    network::GateNetwork,
+    Board, Error, Result,
+};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Builds gate networks from boolean expressions
+#[derive(Debug, Clone)]
+pub struct Synthesizer {
+    outputs: Vec<(String, Expression)>,
+    minimize: bool,
+}
+
+impl Default for Synthesizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Synthesizer {
+    /// Largest number of inputs of an output to be minimized, larger ones are kept as written
+    pub const MAX_MINIMIZED_INPUTS: usize = 10;
+
+    pub fn new() -> Self {
+        Self {
+            outputs: vec![],
+            minimize: true,
+        }
+    }
+
+    /// Enable or disable the minimization of the outputs, enabled by default
+    pub fn set_minimize(&mut self, minimize: bool) {
+        self.minimize = minimize;
+    }
+
+    /// Add an output computed by an expression of the inputs
+    ///
+    /// An expression can use the other outputs as inputs, as long as no output depends on itself.
+    pub fn add_output(&mut self, name: &str, expression: Expression) -> Result<()> {
+        if self.outputs.iter().any(|(output, _)| output == name) {
+            return Err(Error::InvalidLogic(format!(
+                "output `{name}` is defined twice"
+            )));
+        }
+        if let Some(path) = self.find_loop(name, &expression) {
+            return Err(Error::InvalidLogic(format!(
+                "output `{name}` depends on itself through {}",
+                path.join(" -> ")
+            )));
+        }
+        self.outputs.push((String::from(name), expression));
+        Ok(())
+    }
+
+    /// Find the outputs through which `expression` depends on the output `name`
+    fn find_loop(&self, name: &str, expression: &Expression) -> Option<Vec<String>> {
+        let mut visited = HashSet::new();
+        let mut stack: Vec<Vec<String>> = expression
+            .variables()
+            .into_iter()
+            .map(|variable| vec![variable])
+            .collect();
+        while let Some(path) = stack.pop() {
+            // unwrap because paths always have at least a signal
+            let last = path.last().unwrap();
+            if last == name {
+                return Some(path);
+            }
+            if !visited.insert(last.clone()) {
+                continue;
+            }
+            if let Some((_, expression)) = self.outputs.iter().find(|(output, _)| output == last) {
+                for variable in expression.variables() {
+                    let mut next = path.clone();
+                    next.push(variable);
+                    stack.push(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Add an output from an equation of the form `Y = expression`
+    pub fn add_equation(&mut self, equation: &str) -> Result<()> {
+        let (name, expression) = match equation.find('=') {
+            Some(position) => (&equation[..position], &equation[position + 1..]),
+            None => {
+                return Err(Error::Expression(ExpressionError {
+                    column: equation.len() + 1,
+                    message: String::from("expected `=` after the output name"),
+                }))
+            }
+        };
+        let name = match Expression::parse(name)? {
+            Expression::Var(name) => name,
+            _ => {
+                return Err(Error::Expression(ExpressionError {
+                    column: 1,
+                    message: String::from("expected an output name"),
+                }))
+            }
+        };
+        let expression = Expression::parse(expression).map_err(|err| ExpressionError {
+            column: err.column + equation.len() - expression.len(),
+            message: err.message,
+        })?;
+        self.add_output(&name, expression)
+    }
+
+    /// Get the names of the inputs, the signals used by the expressions that are not outputs
+    pub fn get_inputs(&self) -> Vec<String> {
+        let outputs: HashSet<&str> = self.outputs.iter().map(|(name, _)| name.as_str()).collect();
+        let inputs: BTreeSet<String> = self
+            .outputs
+            .iter()
+            .flat_map(|(_, expression)| expression.variables())
+            .filter(|name| !outputs.contains(name.as_str()))
+            .collect();
+        inputs.into_iter().collect()
+    }
+
+    pub fn get_outputs(&self) -> Vec<String> {
+        self.outputs.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Build the gate network computing the outputs
+    pub fn to_network(&self) -> GateNetwork {
+        let structural = self.build_network(false);
+        if !self.minimize {
+            return structural;
+        }
+        let minimized = self.build_network(true);
+        if minimized.chip_count() <= structural.chip_count() {
+            minimized
+        } else {
+            structural
+        }
+    }
+
+    /// Build a board computing the outputs
+    ///
+    /// The inputs and outputs are the traces named after them, see `GateNetwork::build_board`.
+    pub fn build_board(&self) -> Board {
+        let mut board = self.to_network().build_board();
+        // inputs can be optimized away, they still get their trace
+        for input in self.get_inputs() {
+            if board.get_trace_by_name(&input).is_none() {
+                board.new_trace().borrow_mut().set_name(&input);
+            }
+        }
+        board
+    }
+
+    /// Build a chip computing the outputs
+    ///
+    /// Its pins are the inputs in alphabetical order followed by the outputs in the order they were added.
+    pub fn build_subboard(&self, chip_type: &str) -> Result<SubBoard> {
+        let mut chip = SubBoard::new(chip_type, self.build_board());
+        for input in self.get_inputs() {
+            chip.expose_input(&input)?;
+        }
+        for (output, _) in &self.outputs {
+            chip.expose_output(output)?;
+        }
+        Ok(chip)
+    }
+
+    fn build_network(&self, minimize: bool) -> GateNetwork {
+        let mut builder = Builder::new(self);
+        for (output, expression) in &self.outputs {
+            let expression = fold(expression);
+            let variables = expression.variables();
+            if minimize && variables.len() <= Self::MAX_MINIMIZED_INPUTS {
+                builder.sum_of_products(&expression, &variables, output);
+            } else {
+                builder.emit(&expression, Some(output));
+            }
+        }
+        builder.network
+    }
+}
+
+/// A product term: a set of literals (signal, true if inverted)
+type Product = Vec<(String, bool)>;
+
+/// Emits the gates into a network, sharing the inverted inputs and product terms between outputs
+struct Builder {
+    network: GateNetwork,
+    used: HashSet<String>,
+    next: usize,
+    inverted: HashMap<String, String>,
+    products: HashMap<Product, String>,
+}
+
+impl Builder {
+    fn new(synthesizer: &Synthesizer) -> Self {
+        let mut used: HashSet<String> = synthesizer.get_inputs().into_iter().collect();
+        used.extend(synthesizer.get_outputs());
+        Self {
+            network: GateNetwork::new(),
+            used,
+            next: 1,
+            inverted: HashMap::new(),
+            products: HashMap::new(),
+        }
+    }
+
+    fn intermediate(&mut self) -> String {
+        loop {
+            let name = format!("_s{}", self.next);
+            self.next += 1;
+            if self.used.insert(name.clone()) {
+                return name;
+            }
+        }
+    }
+
+    /// Drive `target` with a gate, or get a new net driven by it
+    fn gate(&mut self, kind: GateKind, inputs: &[&str], target: Option<&str>) -> String {
+        let output = match target {
+            Some(target) => String::from(target),
+            None => self.intermediate(),
+        };
+        // unwrap because expressions never give a gate without input, and NOT gates have a single one
+        self.network.add_gate(kind, inputs, &output).unwrap();
+        output
+    }
+
+    /// Drive `target` with a net, a single input gate is turned into a buffer by the network
+    fn drive(&mut self, net: String, target: Option<&str>) -> String {
+        match target {
+            Some(target) => self.gate(GateKind::And, &[&net], Some(target)),
+            None => net,
+        }
+    }
+
+    fn invert(&mut self, net: &str, target: Option<&str>) -> String {
+        if target.is_some() {
+            return self.gate(GateKind::Not, &[net], target);
+        }
+        if let Some(inverted) = self.inverted.get(net) {
+            return inverted.clone();
+        }
+        let inverted = self.gate(GateKind::Not, &[net], None);
+        self.inverted.insert(String::from(net), inverted.clone());
+        inverted
+    }
+
+    /// Emit the gates of an expression as written
+    fn emit(&mut self, expression: &Expression, target: Option<&str>) -> String {
+        match expression {
+            Expression::Const(constant) => {
+                let net = if *constant {
+                    GateNetwork::HIGH
+                } else {
+                    GateNetwork::LOW
+                };
+                self.drive(String::from(net), target)
+            }
+            Expression::Var(name) => self.drive(name.clone(), target),
+            Expression::Not(inner) => match &**inner {
+                Expression::Var(name) => self.invert(name, target),
+                Expression::And(terms) => self.terms(GateKind::Nand, terms, target),
+                Expression::Or(terms) => self.terms(GateKind::Nor, terms, target),
+                _ => {
+                    let net = self.emit(inner, None);
+                    self.invert(&net, target)
+                }
+            },
+            Expression::And(terms) => self.terms(GateKind::And, terms, target),
+            Expression::Or(terms) => self.terms(GateKind::Or, terms, target),
+            Expression::Xor(terms) => {
+                let nets: Vec<String> = terms.iter().map(|term| self.emit(term, None)).collect();
+                let mut result = nets[0].clone();
+                for (index, net) in nets.iter().enumerate().skip(1) {
+                    let last = index == nets.len() - 1;
+                    result = self.xor(&result, net, if last { target } else { None });
+                }
+                result
+            }
+        }
+    }
+
+    fn terms(&mut self, kind: GateKind, terms: &[Expression], target: Option<&str>) -> String {
+        let nets: Vec<String> = terms.iter().map(|term| self.emit(term, None)).collect();
+        let inputs: Vec<&str> = nets.iter().map(String::as_str).collect();
+        self.gate(kind, &inputs, target)
+    }
+
+    /// A XOR made of 4 NAND gates, a single chip
+    fn xor(&mut self, a: &str, b: &str, target: Option<&str>) -> String {
+        let both = self.gate(GateKind::Nand, &[a, b], None);
+        let not_b = self.gate(GateKind::Nand, &[a, &both], None);
+        let not_a = self.gate(GateKind::Nand, &[b, &both], None);
+        self.gate(GateKind::Nand, &[&not_b, &not_a], target)
+    }
+
+    /// Emit a minimized sum of products of an expression, an AND plane followed by an OR gate
+    fn sum_of_products(&mut self, expression: &Expression, variables: &[String], output: &str) {
+        let minterms: Vec<u32> = (0..1u32 << variables.len())
+            .filter(|row| {
+                let value = |name: &str| {
+                    let index = variables.iter().position(|variable| variable == name)?;
+                    Some((row >> index) & 1 == 1)
+                };
+                expression.eval(&value) == Some(true)
+            })
+            .collect();
+        let products: Vec<Product> = minimize(variables.len(), &minterms)
+            .into_iter()
+            .map(|(value, mask)| {
+                (0..variables.len())
+                    .filter(|index| mask & (1 << index) == 0)
+                    .map(|index| (variables[index].clone(), value & (1 << index) == 0))
+                    .collect()
+            })
+            .collect();
+        match products.len() {
+            0 => {
+                self.drive(String::from(GateNetwork::LOW), Some(output));
+            }
+            1 => {
+                self.product(&products[0], Some(output));
+            }
+            _ => {
+                let nets: Vec<String> = products
+                    .iter()
+                    .map(|product| self.product(product, None))
+                    .collect();
+                let inputs: Vec<&str> = nets.iter().map(String::as_str).collect();
+                self.gate(GateKind::Or, &inputs, Some(output));
+            }
+        }
+    }
+
+    fn product(&mut self, product: &Product, target: Option<&str>) -> String {
+        match product.as_slice() {
+            [] => self.drive(String::from(GateNetwork::HIGH), target),
+            [(name, true)] => self.invert(name, target),
+            [(name, false)] => self.drive(name.clone(), target),
+            _ => {
+                if target.is_none() {
+                    if let Some(net) = self.products.get(product) {
+                        return net.clone();
+                    }
+                }
+                let nets: Vec<String> = product
+                    .iter()
+                    .map(|(name, inverted)| {
+                        if *inverted {
+                            self.invert(name, None)
+                        } else {
+                            name.clone()
+                        }
+                    })
+                    .collect();
+                let inputs: Vec<&str> = nets.iter().map(String::as_str).collect();
+                let net = self.gate(GateKind::And, &inputs, target);
+                self.products.insert(product.clone(), net.clone());
+                net
+            }
+        }
+    }
+}
+
+/// Fold the constants and flatten the nested operations of an expression
+fn fold(expression: &Expression) -> Expression {
+    match expression {
+        Expression::Const(_) | Expression::Var(_) => expression.clone(),
+        Expression::Not(inner) => match fold(inner) {
+            Expression::Const(constant) => Expression::Const(!constant),
+            Expression::Not(inner) => *inner,
+            inner => Expression::Not(Box::new(inner)),
+        },
+        Expression::And(terms) => {
+            let mut folded = vec![];
+            for term in terms {
+                match fold(term) {
+                    Expression::Const(false) => return Expression::Const(false),
+                    Expression::Const(true) => {}
+                    Expression::And(inner) => folded.extend(inner),
+                    term => folded.push(term),
+                }
+            }
+            join(folded, true, Expression::And)
+        }
+        Expression::Or(terms) => {
+            let mut folded = vec![];
+            for term in terms {
+                match fold(term) {
+                    Expression::Const(true) => return Expression::Const(true),
+                    Expression::Const(false) => {}
+                    Expression::Or(inner) => folded.extend(inner),
+                    term => folded.push(term),
+                }
+            }
+            join(folded, false, Expression::Or)
+        }
+        Expression::Xor(terms) => {
+            let mut folded = vec![];
+            let mut inverted = false;
+            for term in terms {
+                match fold(term) {
+                    Expression::Const(constant) => inverted ^= constant,
+                    Expression::Xor(inner) => folded.extend(inner),
+                    term => folded.push(term),
+                }
+            }
+            let result = join(folded, false, Expression::Xor);
+            if inverted {
+                fold(&Expression::Not(Box::new(result)))
+            } else {
+                result
+            }
+        }
+    }
+}
+
+/// Build an operation from its folded terms, `empty` is its value without any term
+fn join(
+    mut terms: Vec<Expression>,
+    empty: bool,
+    operation: fn(Vec<Expression>) -> Expression,
+) -> Expression {
+    match terms.len() {
+        0 => Expression::Const(empty),
+        1 => terms.remove(0),
+        _ => operation(terms),
+    }
+}
+
+/// Minimize a function of `inputs` inputs true for the `minterms` with the Quine-McCluskey method
+/// Returns the implicants of the cover as (value, mask of the inputs it doesn't depend on).
+fn minimize(inputs: usize, minterms: &[u32]) -> Vec<(u32, u32)> {
+    let mut primes: Vec<(u32, u32)> = vec![];
+    let mut current: BTreeSet<(u32, u32)> = minterms.iter().map(|minterm| (*minterm, 0)).collect();
+    while !current.is_empty() {
+        let mut next = BTreeSet::new();
+        let mut combined = HashSet::new();
+        let implicants: Vec<(u32, u32)> = current.iter().copied().collect();
+        for (index, (value, mask)) in implicants.iter().enumerate() {
+            for (other_value, other_mask) in &implicants[index + 1..] {
+                let difference = value ^ other_value;
+                if mask == other_mask && difference.count_ones() == 1 {
+                    next.insert((value & !difference, mask | difference));
+                    combined.insert((*value, *mask));
+                    combined.insert((*other_value, *other_mask));
+                }
+            }
+        }
+        primes.extend(
+            implicants
+                .into_iter()
+                .filter(|implicant| !combined.contains(implicant)),
+        );
+        current = next;
+    }
+
+    let covers = |(value, mask): (u32, u32), minterm: u32| minterm & !mask == value;
+    let literals = |(_, mask): (u32, u32)| inputs as u32 - mask.count_ones();
+    let mut uncovered: BTreeSet<u32> = minterms.iter().copied().collect();
+    let mut cover = vec![];
+    // essential primes: the only ones covering a minterm
+    for minterm in minterms {
+        let mut covering = primes.iter().filter(|prime| covers(**prime, *minterm));
+        if let (Some(prime), None) = (covering.next(), covering.next()) {
+            if !cover.contains(prime) {
+                cover.push(*prime);
+            }
+        }
+    }
+    uncovered.retain(|minterm| !cover.iter().any(|prime| covers(*prime, *minterm)));
+    // then the primes covering the most remaining minterms with the fewest literals
+    while !uncovered.is_empty() {
+        let best = primes
+            .iter()
+            .copied()
+            .max_by_key(|prime| {
+                let count = uncovered
+                    .iter()
+                    .filter(|minterm| covers(*prime, **minterm))
+                    .count();
+                (count, std::cmp::Reverse(literals(*prime)))
+            })
+            .unwrap_or_default();
+        uncovered.retain(|minterm| !covers(best, *minterm));
+        cover.push(best);
+    }
+    cover
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+
+    /// Evaluate a signal of the synthesizer for a row of its inputs
+    fn value(synthesizer: &Synthesizer, inputs: &[String], row: usize, name: &str) -> Option<bool> {
+        match inputs.iter().position(|input| input == name) {
+            Some(index) => Some((row >> index) & 1 == 1),
+            None => synthesizer
+                .outputs
+                .iter()
+                .find(|(output, _)| output == name)?
+                .1
+                .eval(&|name| value(synthesizer, inputs, row, name)),
+        }
+    }
+
+    /// Compare the truth table of the synthesized board with the evaluation of the equations
+    fn check(equations: &[&str]) {
+        for minimize in [false, true] {
+            let mut synthesizer = Synthesizer::new();
+            synthesizer.set_minimize(minimize);
+            for equation in equations {
+                synthesizer.add_equation(equation).unwrap();
+            }
+            let inputs = synthesizer.get_inputs();
+            let outputs = synthesizer.get_outputs();
+            let input_names: Vec<&str> = inputs.iter().map(String::as_str).collect();
+            let output_names: Vec<&str> = outputs.iter().map(String::as_str).collect();
+            let table = synthesizer
+                .build_board()
+                .truth_table(&input_names, &output_names)
+                .unwrap();
+            for (row, states) in table.get_rows().iter().enumerate() {
+                for (output, state) in outputs.iter().zip(states) {
+                    let expected = value(&synthesizer, &inputs, row, output).unwrap();
+                    assert_eq!(
+                        *state,
+                        State::from(expected),
+                        "{} for row {} of {:?}, minimized: {}",
+                        output,
+                        row,
+                        equations,
+                        minimize
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn xors_match_their_expression() {
+        check(&["Y = A ^ B", "Z = A ^ B ^ C", "W = !(A ^ C)"]);
+    }
+
+    #[test]
+    fn constants_match_their_expression() {
+        check(&["Y = 1", "Z = 0"]);
+        check(&["Y = A & 0 | 1", "Z = A | 0", "W = (A ^ 1) & B"]);
+    }
+
+    #[test]
+    fn full_adders_match_their_expression() {
+        check(&["S = A ^ B ^ C", "CO = A & B | C & (A ^ B)"]);
+        check(&[
+            "S0 = A0 ^ B0",
+            "C0 = A0 & B0",
+            "S1 = A1 ^ B1 ^ C0",
+            "C1 = A1 & B1 | C0 & (A1 | B1)",
+        ]);
+    }
+
+    #[test]
+    fn outputs_can_be_inputs_of_other_outputs() {
+        check(&["P = A ^ B", "S = P ^ C", "CO = A & B | P & C"]);
+        check(&["Y = !X", "X = A & B"]);
+    }
+
+    #[test]
+    fn outputs_depending_on_themselves_are_rejected() {
+        let mut synthesizer = Synthesizer::new();
+        assert!(matches!(
+            synthesizer.add_equation("A = A & B"),
+            Err(Error::InvalidLogic(_))
+        ));
+        synthesizer.add_equation("A = B").unwrap();
+        synthesizer.add_equation("C = A | D").unwrap();
+        assert!(matches!(
+            synthesizer.add_equation("B = C"),
+            Err(Error::InvalidLogic(_))
+        ));
+        assert_eq!(synthesizer.get_outputs(), ["A", "C"]);
+    }
+}
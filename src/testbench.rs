@@ -0,0 +1,534 @@
+//! Scripted test benches
+//!
+//! A `TestBench` lists timed steps run on a board: setting signals, pressing buttons,
+//! running the simulation and checking the states of signals, buses and memories.
+//! It's written in RON and can point to a saved board, its path being relative to the test bench file:
+//! ```text
+//! TestBench(
+//!     board: Some("adder.ron"),
+//!     steps: [
+//!         Set(Trace("A"), High),
+//!         Set(Pin("U1", 2), Low),
+//!         Press("SW1"),
+//!         Run(10),
+//!         Expect(Trace("S"), High),
+//!         ExpectBus([Trace("S"), Trace("CO")], 0x1),
+//!         Release("SW1"),
+//!         ExpectMemory(socket: "RAM", address: 0x10, data: [0x2A, 0x00]),
+//!     ],
+//! )
+//! ```
+//! Running it gives a `Report` with the outcome of every step and the simulated time it happened at.
+use super::{
+    chip::{
+        buttons::Button,
+        memory::{Ram256B, Rom256B},
+        ChipRegistry,
+    },
+    fault::{self, Fault, FaultReport},
+    Board, Chip, Error, Result, SaveFormat, State,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+
+/// A signal of the board
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Signal {
+    /// A trace, by name
+    Trace(String),
+    /// A pin of a chip, by socket name and pin number
+    /// Setting a pin only lasts if it's not linked to a trace, use `Trace` for the inputs of a circuit.
+    Pin(String, u8),
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Trace(name) => write!(f, "{name}"),
+            Self::Pin(socket, pin) => write!(f, "{socket}.{pin}"),
+        }
+    }
+}
+
+/// A step of a test bench
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Step {
+    /// Set the state of a signal, setting a trace to `Undefined` stops forcing it
+    Set(Signal, State),
+    /// Press the button plugged in a socket, by name
+    Press(String),
+    /// Release the button plugged in a socket, by name
+    Release(String),
+    /// Run the simulation for a number of milliseconds
+    Run(u64),
+    /// Check the state of a signal
+    Expect(Signal, State),
+    /// Check the value of a bus of up to 64 signals, its first signal being the least significant bit
+    ExpectBus(Vec<Signal>, u64),
+    /// Check the content of a RAM or a ROM from an address
+    ExpectMemory {
+        socket: String,
+        address: u8,
+        data: Vec<u8>,
+    },
+}
+
+/// A list of steps to run on a board
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestBench {
+    /// Path of the saved board to test, relative to the test bench file
+    /// Its format is given by its extension: `.json`, `.cbor` or RON otherwise.
+    #[serde(default)]
+    pub board: Option<String>,
+    /// Duration of a simulation step in microseconds
+    #[serde(default = "default_step")]
+    pub step: u64,
+    pub steps: Vec<Step>,
+}
+
+fn default_step() -> u64 {
+    1000
+}
+
+/// The outcome of a step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    /// The step isn't a check and was run
+    Done,
+    Passed,
+    /// The check failed, or the step couldn't be run
+    Failed(String),
+}
+
+/// The outcome of a step and the simulated time it happened at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepResult {
+    /// Index of the step in the test bench, starting at 0
+    pub index: usize,
+    pub time: Duration,
+    pub step: Step,
+    pub outcome: Outcome,
+}
+
+/// The results of every step of a test bench
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Report {
+    pub results: Vec<StepResult>,
+}
+
+impl Report {
+    /// Returns true if no step failed
+    pub fn passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &StepResult> {
+        self.results
+            .iter()
+            .filter(|result| matches!(result.outcome, Outcome::Failed(_)))
+    }
+
+    /// Count the checks that passed and the ones that failed
+    pub fn get_score(&self) -> (usize, usize) {
+        let passed = self
+            .results
+            .iter()
+            .filter(|result| result.outcome == Outcome::Passed)
+            .count();
+        (passed, self.failures().count())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.results {
+            let outcome = match &result.outcome {
+                Outcome::Done => String::from("done"),
+                Outcome::Passed => String::from("passed"),
+                Outcome::Failed(message) => format!("FAILED: {message}"),
+            };
+            writeln!(
+                f,
+                "[{:>10.3} ms] step {}: {:?} {}",
+                result.time.as_secs_f64() * 1000.0,
+                result.index + 1,
+                result.step,
+                outcome
+            )?;
+        }
+        let (passed, failed) = self.get_score();
+        write!(f, "{passed} passed, {failed} failed")
+    }
+}
+
+impl TestBench {
+    /// Read a test bench from a RON string
+    pub fn load_from_str(input: &str) -> Result<Self> {
+        Ok(ron::de::from_str(input)?)
+    }
+
+    /// Read a test bench from a RON file
+    pub fn load(filepath: &str) -> Result<Self> {
+        Ok(ron::de::from_reader(File::open(filepath)?)?)
+    }
+
+    /// Load a test bench file and the board it points to, then run it
+    /// The test bench must give a `board`.
+    pub fn run_file(filepath: &str, registry: &ChipRegistry) -> Result<Report> {
+        let bench = Self::load(filepath)?;
+        let board_path = bench.board.as_ref().ok_or_else(|| {
+            Error::InvalidTestBench(String::from("the test bench doesn't give a board"))
+        })?;
+        let board_path = Path::new(filepath)
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(board_path);
+        let format = match board_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("json") => SaveFormat::Json,
+            Some("cbor") => SaveFormat::Cbor,
+            _ => SaveFormat::Ron,
+        };
+        let mut board = Board::load_from_reader(File::open(board_path)?, format, registry)?;
+        Ok(bench.run(&mut board))
+    }
+
+    /// Run the test bench without fault, then once per fault from the same initial state
+    ///
+    /// The times of the faults are relative to the start of the test bench.
+    /// A fault is detected when the outcome of a step differs from the run without fault.
+    /// The board is put back in its initial state afterward.
+    pub fn run_with_faults(&self, board: &mut Board, faults: &[Fault]) -> Result<FaultReport> {
+        fault::campaign(self, board, faults)
+    }
+
+    /// Run the steps on a board
+    /// Steps that can't be run (unknown signal, no button ...) fail and the next ones are still run.
+    pub fn run(&self, board: &mut Board) -> Report {
+        let step = Duration::from_micros(self.step.max(1));
+        let mut report = Report::default();
+        for (index, test_step) in self.steps.iter().enumerate() {
+            let outcome = match run_step(board, test_step, step) {
+                Ok(outcome) => outcome,
+                Err(message) => Outcome::Failed(message),
+            };
+            report.results.push(StepResult {
+                index,
+                time: board.get_elapsed(),
+                step: test_step.clone(),
+                outcome,
+            });
+        }
+        report
+    }
+}
+
+fn run_step(
+    board: &mut Board,
+    step: &Step,
+    duration: Duration,
+) -> std::result::Result<Outcome, String> {
+    match step {
+        Step::Set(signal, state) => {
+            set(board, signal, state)?;
+            Ok(Outcome::Done)
+        }
+        Step::Press(socket) | Step::Release(socket) => {
+            let socket = find_socket(board, socket)?;
+            let mut socket = socket.borrow_mut();
+            let button = socket
+                .get_chip_as::<Button>()
+                .ok_or_else(|| String::from("the socket doesn't hold a button"))?;
+            if let Step::Press(_) = step {
+                button.press();
+            } else {
+                button.release();
+            }
+            Ok(Outcome::Done)
+        }
+        Step::Run(milliseconds) => {
+            board.run_during(Duration::from_millis(*milliseconds), duration);
+            Ok(Outcome::Done)
+        }
+        Step::Expect(signal, expected) => {
+            let actual = get(board, signal)?;
+            if actual == *expected {
+                Ok(Outcome::Passed)
+            } else {
+                Ok(Outcome::Failed(format!(
+                    "{signal} is {actual:?}, expected {expected:?}"
+                )))
+            }
+        }
+        Step::ExpectBus(signals, expected) => {
+            if signals.len() > 64 {
+                return Err(format!(
+                    "the bus has {} signals, the maximum is 64",
+                    signals.len()
+                ));
+            }
+            let mut value = 0u64;
+            for (bit, signal) in signals.iter().enumerate() {
+                match get(board, signal)? {
+                    State::High => value |= 1 << bit,
+                    State::Low => {}
+                    State::Undefined => {
+                        return Ok(Outcome::Failed(format!("{signal} is undefined")));
+                    }
+                }
+            }
+            if value == *expected {
+                Ok(Outcome::Passed)
+            } else {
+                Ok(Outcome::Failed(format!(
+                    "the bus is 0x{value:X}, expected 0x{expected:X}"
+                )))
+            }
+        }
+        Step::ExpectMemory {
+            socket,
+            address,
+            data,
+        } => {
+            let socket = find_socket(board, socket)?;
+            let mut socket = socket.borrow_mut();
+            let memory = match socket.get_chip_as::<Ram256B>() {
+                Some(ram) => *ram.get_ram(),
+                None => *socket
+                    .get_chip_as::<Rom256B>()
+                    .ok_or_else(|| String::from("the socket doesn't hold a RAM or a ROM"))?
+                    .get_rom(),
+            };
+            for (offset, expected) in data.iter().enumerate() {
+                let address = *address as usize + offset;
+                match memory.get(address) {
+                    Some(actual) if actual == expected => {}
+                    Some(actual) => {
+                        return Ok(Outcome::Failed(format!(
+                            "0x{address:02X} holds 0x{actual:02X}, expected 0x{expected:02X}"
+                        )));
+                    }
+                    None => return Err(format!("address 0x{address:X} is out of the memory")),
+                }
+            }
+            Ok(Outcome::Passed)
+        }
+    }
+}
+
+fn find_socket(
+    board: &Board,
+    name: &str,
+) -> std::result::Result<std::rc::Rc<std::cell::RefCell<super::Socket>>, String> {
+    board
+        .get_socket_by_name(name)
+        .ok_or_else(|| format!("unknown socket `{name}`"))
+}
+
+fn set(board: &Board, signal: &Signal, state: &State) -> std::result::Result<(), String> {
+    match signal {
+        Signal::Trace(name) => {
+            let trace = board
+                .get_trace_by_name(name)
+                .ok_or_else(|| format!("unknown trace `{name}`"))?;
+            match state {
+                State::Undefined => trace.borrow_mut().release(),
+                state => trace.borrow_mut().force(state.clone()),
+            }
+        }
+        Signal::Pin(socket, pin) => {
+            find_socket(board, socket)?
+                .borrow_mut()
+                .get_pin(*pin)
+                .map_err(|err| err.to_string())?
+                .borrow_mut()
+                .state = state.clone();
+        }
+    }
+    Ok(())
+}
+
+fn get(board: &Board, signal: &Signal) -> std::result::Result<State, String> {
+    match signal {
+        Signal::Trace(name) => Ok(board
+            .get_trace_by_name(name)
+            .ok_or_else(|| format!("unknown trace `{name}`"))?
+            .borrow()
+            .get_state()),
+        Signal::Pin(socket, pin) => Ok(find_socket(board, socket)?
+            .borrow_mut()
+            .get_pin(*pin)
+            .map_err(|err| err.to_string())?
+            .borrow()
+            .state
+            .clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVERTER: &str = "part PWR virt_ic::Generator
+                            part U1 virt_ic::GateNot
+                            net VCC PWR.1 U1.14
+                            net GND PWR.2 U1.7
+                            net A U1.1
+                            net Y U1.2";
+
+    fn inverter() -> Board {
+        Board::from_netlist(INVERTER, &ChipRegistry::new()).unwrap()
+    }
+
+    fn trace(name: &str) -> Signal {
+        Signal::Trace(String::from(name))
+    }
+
+    fn outcomes(bench: &TestBench, board: &mut Board) -> Vec<Outcome> {
+        bench
+            .run(board)
+            .results
+            .into_iter()
+            .map(|result| result.outcome)
+            .collect()
+    }
+
+    #[test]
+    fn test_benches_are_read_from_ron() {
+        let bench = TestBench::load_from_str(
+            r#"TestBench(
+                steps: [
+                    Set(Trace("A"), High),
+                    Set(Pin("U1", 2), Low),
+                    Press("SW1"),
+                    Run(10),
+                    Expect(Trace("S"), High),
+                    ExpectBus([Trace("S"), Trace("CO")], 0x1),
+                    Release("SW1"),
+                    ExpectMemory(socket: "RAM", address: 0x10, data: [0x2A, 0x00]),
+                ],
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(bench.board, None);
+        assert_eq!(bench.step, 1000);
+        assert_eq!(bench.steps.len(), 8);
+        assert_eq!(
+            bench.steps[1],
+            Step::Set(Signal::Pin(String::from("U1"), 2), State::Low)
+        );
+        assert_eq!(
+            bench.steps[7],
+            Step::ExpectMemory {
+                socket: String::from("RAM"),
+                address: 0x10,
+                data: vec![0x2A, 0x00],
+            }
+        );
+        assert!(TestBench::load_from_str("TestBench(steps: [Wait(10)])").is_err());
+    }
+
+    #[test]
+    fn expectations_pass_and_fail() {
+        let bench = TestBench {
+            board: None,
+            step: 1000,
+            steps: vec![
+                Step::Set(trace("A"), State::High),
+                Step::Run(2),
+                Step::Expect(trace("Y"), State::Low),
+                Step::Expect(trace("Y"), State::High),
+                Step::Expect(trace("Z"), State::High),
+                Step::Expect(trace("Y"), State::Low),
+            ],
+        };
+        let mut board = inverter();
+        let report = bench.run(&mut board);
+        assert_eq!(report.get_score(), (2, 2));
+        assert!(!report.passed());
+        let outcomes: Vec<Outcome> = report.results.into_iter().map(|r| r.outcome).collect();
+        assert_eq!(
+            outcomes[..3],
+            [Outcome::Done, Outcome::Done, Outcome::Passed]
+        );
+        assert_eq!(
+            outcomes[3],
+            Outcome::Failed(String::from("Y is Low, expected High"))
+        );
+        assert_eq!(
+            outcomes[4],
+            Outcome::Failed(String::from("unknown trace `Z`"))
+        );
+        // a failed step doesn't stop the next ones
+        assert_eq!(outcomes[5], Outcome::Passed);
+    }
+
+    #[test]
+    fn buses_are_read_from_their_first_signal() {
+        let bus = |signals: Vec<Signal>, value| TestBench {
+            board: None,
+            step: 1000,
+            steps: vec![
+                Step::Set(trace("A"), State::Low),
+                Step::Run(2),
+                Step::ExpectBus(signals, value),
+            ],
+        };
+        let mut board = inverter();
+        let outcome = |bench: TestBench, board: &mut Board| outcomes(&bench, board).remove(2);
+        assert_eq!(
+            outcome(bus(vec![trace("A"), trace("Y")], 0b10), &mut board),
+            Outcome::Passed
+        );
+        assert_eq!(
+            outcome(bus(vec![trace("Y"), trace("A")], 0b10), &mut board),
+            Outcome::Failed(String::from("the bus is 0x1, expected 0x2"))
+        );
+        let mut wide = vec![trace("A"); 63];
+        wide.push(trace("Y"));
+        assert_eq!(
+            outcome(bus(wide.clone(), 1 << 63), &mut board),
+            Outcome::Passed
+        );
+        wide.push(trace("Y"));
+        assert!(matches!(
+            outcome(bus(wide, 0), &mut board),
+            Outcome::Failed(_)
+        ));
+    }
+
+    #[test]
+    fn files_point_to_their_board() {
+        let directory =
+            std::env::temp_dir().join(format!("virt_ic_testbench_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        inverter()
+            .save_as(
+                directory.join("inverter.json").to_str().unwrap(),
+                SaveFormat::Json,
+            )
+            .unwrap();
+        let bench_path = directory.join("bench.ron");
+        std::fs::write(
+            &bench_path,
+            r#"TestBench(
+                board: Some("inverter.json"),
+                steps: [Set(Trace("A"), Low), Run(2), Expect(Trace("Y"), High)],
+            )"#,
+        )
+        .unwrap();
+        let report = TestBench::run_file(bench_path.to_str().unwrap(), &ChipRegistry::new());
+        std::fs::write(&bench_path, "TestBench(steps: [])").unwrap();
+        let without_board = TestBench::run_file(bench_path.to_str().unwrap(), &ChipRegistry::new());
+        std::fs::remove_dir_all(&directory).unwrap();
+        let report = report.unwrap();
+        assert!(report.passed());
+        assert_eq!(report.get_score(), (1, 0));
+        assert!(matches!(without_board, Err(Error::InvalidTestBench(_))));
+    }
+}
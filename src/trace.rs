@@ -1,4 +1,4 @@
-use super::{save::SavedTrace, Pin, PinType, State};
+use super::{chip::analog, save::SavedTrace, LogicModel, Pin, PinType, State, StdLogic};
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -6,18 +6,109 @@ use std::rc::Rc;
 #[derive(Default, Debug)]
 pub struct Trace {
     link: Vec<Rc<RefCell<Pin>>>,
+    name: Option<String>,
+    forced: Option<State>,
+    model: LogicModel,
+    /// One of the pins is analog
+    analog: bool,
 }
 
 impl Trace {
     pub fn new() -> Self {
-        Self { link: vec![] }
+        Self {
+            link: vec![],
+            name: None,
+            forced: None,
+            model: LogicModel::Binary,
+            analog: false,
+        }
+    }
+
+    /// Get the name of the trace, if it has one
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Give a name to the trace, used by netlists and exports
+    pub fn set_name(&mut self, name: &str) {
+        self.name = Some(String::from(name));
+    }
+
+    /// Get the pins linked by this trace
+    pub fn get_pins(&self) -> Vec<Rc<RefCell<Pin>>> {
+        self.link.clone()
     }
 
     pub fn connect(&mut self, pin: Rc<RefCell<Pin>>) {
+        self.analog |= pin.borrow().analog;
         self.link.push(pin);
     }
 
-    pub fn communicate(&mut self) {
+    /// Force the state of the trace whatever its output pins drive, to set the inputs of a circuit
+    /// The forced state isn't saved with the board.
+    pub fn force(&mut self, state: State) {
+        self.forced = Some(state);
+    }
+
+    /// Stop forcing the state of the trace
+    pub fn release(&mut self) {
+        self.forced = None;
+    }
+
+    pub fn get_forced(&self) -> Option<State> {
+        self.forced.clone()
+    }
+
+    /// Set how the trace resolves the states of its output pins, see `LogicModel`
+    pub fn set_logic_model(&mut self, model: LogicModel) {
+        self.model = model;
+    }
+
+    /// Get the level driven on the trace by the output pins with the IEEE 1164 resolution table,
+    /// or its forced state
+    pub fn get_level(&self) -> StdLogic {
+        if let Some(forced) = &self.forced {
+            return StdLogic::from(forced.clone());
+        }
+        StdLogic::resolve_all(
+            self.link
+                .iter()
+                .map(|pin| pin.borrow())
+                .filter(|pin| pin.pin_type == PinType::Output)
+                .map(|pin| pin.get_level()),
+        )
+    }
+
+    /// Get the voltage driven on the trace: the mean of the voltages of its output pins,
+    /// as if they all had the same impedance, or the voltage of its forced state
+    /// Digital outputs drive `analog::HIGH_VOLTAGE` or `analog::LOW_VOLTAGE`.
+    /// Returns `None` if no output drives the trace.
+    pub fn get_voltage(&self) -> Option<f64> {
+        if let Some(forced) = &self.forced {
+            return analog::to_voltage(forced);
+        }
+        let (sum, count) = self
+            .link
+            .iter()
+            .map(|pin| pin.borrow())
+            .filter(|pin| pin.pin_type == PinType::Output)
+            .filter_map(|pin| analog::to_voltage(&pin.state).or(pin.voltage))
+            .fold((0.0, 0), |(sum, count), voltage| (sum + voltage, count + 1));
+        if count == 0 {
+            None
+        } else {
+            Some(sum / count as f64)
+        }
+    }
+
+    /// Get the state driven on the trace by the output pins, or its forced state
+    pub fn get_state(&self) -> State {
+        if let Some(forced) = &self.forced {
+            return forced.clone();
+        }
+        if self.model == LogicModel::StdLogic {
+            return State::from(self.get_level());
+        }
         let mut main_state = State::Undefined;
         for pin in &self.link {
             if pin.borrow().pin_type == PinType::Output {
@@ -32,6 +123,28 @@ impl Trace {
                 }
             }
         }
+        main_state
+    }
+
+    pub fn communicate(&mut self) {
+        if self.analog {
+            let voltage = self.get_voltage();
+            for pin in &mut self.link {
+                if pin.borrow().pin_type != PinType::Output {
+                    pin.borrow_mut().voltage = voltage;
+                }
+            }
+        }
+        if self.model == LogicModel::StdLogic {
+            let level = self.get_level();
+            for pin in &mut self.link {
+                if pin.borrow().pin_type != PinType::Output {
+                    pin.borrow_mut().set_level(level);
+                }
+            }
+            return;
+        }
+        let main_state = self.get_state();
         for pin in &mut self.link {
             if pin.borrow().pin_type != PinType::Output {
                 pin.borrow_mut().state = main_state.clone();
@@ -41,6 +154,7 @@ impl Trace {
 
     pub fn save(&self) -> SavedTrace {
         let mut save = SavedTrace::new();
+        save.name = self.name.clone();
         for pin in &self.link {
             save.add_trace(pin.borrow().clone());
         }
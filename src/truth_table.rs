@@ -0,0 +1,321 @@
+//! Truth tables observed on boards
+//!
+//! `Board::truth_table` forces every combination of some input traces,
+//! settles the circuit and records the state of some output traces.
+//! The result can be checked against the expected outputs:
+//! ```
+//! use virt_ic::{chip::ChipRegistry, Board};
+//!
+//! let mut board = Board::from_netlist(
+//!     "part PWR virt_ic::Generator
+//!      part U1 virt_ic::GateNand
+//!      net VCC PWR.1 U1.14
+//!      net GND PWR.2 U1.7
+//!      net A U1.1
+//!      net B U1.2
+//!      net Y U1.3",
+//!     &ChipRegistry::new(),
+//! )
+//! .unwrap();
+//! let table = board.truth_table(&["A", "B"], &["Y"]).unwrap();
+//! assert!(table.check(|inputs| vec![!(inputs[0] && inputs[1])]).is_empty());
+//! ```
+//! The truth table of a network of gate chips is computed much faster once compiled,
+//! with `CompiledNetwork::truth_table`.
+use super::{Board, Error, Result, State};
+use std::fmt;
+use std::time::Duration;
+
+/// The outputs observed for every combination of the inputs
+///
+/// Rows are indexed by the inputs, the first input being the least significant bit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruthTable {
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    rows: Vec<Vec<State>>,
+}
+
+/// An output that doesn't have its expected state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Names and states of the inputs
+    pub inputs: Vec<(String, bool)>,
+    pub output: String,
+    pub expected: State,
+    pub actual: State,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, state) in &self.inputs {
+            write!(f, "{}={} ", name, u8::from(*state))?;
+        }
+        write!(
+            f,
+            "-> {} is {:?}, expected {:?}",
+            self.output, self.actual, self.expected
+        )
+    }
+}
+
+impl TruthTable {
+    /// Largest number of inputs, the table has 2^inputs rows
+    pub const MAX_INPUTS: usize = 16;
+    /// Duration of a simulation step while settling the circuit
+    pub const STEP: Duration = Duration::from_millis(1);
+    /// Largest number of steps to settle the circuit, a combination that doesn't settle has undefined outputs
+    pub const MAX_STEPS: usize = 1000;
+
+    /// Create a table from a function giving the outputs for each combination of the inputs
+    pub fn from_fn<F>(inputs: &[&str], outputs: &[&str], function: F) -> Result<Self>
+    where
+        F: Fn(&[bool]) -> Vec<bool>,
+    {
+        check_inputs(inputs.len())?;
+        let mut rows = Vec::with_capacity(1 << inputs.len());
+        for row in 0..1usize << inputs.len() {
+            let values = function(&bits(row, inputs.len()));
+            if values.len() != outputs.len() {
+                return Err(Error::InvalidLogic(format!(
+                    "{} outputs expected, found {} for row {row}",
+                    outputs.len(),
+                    values.len()
+                )));
+            }
+            rows.push(values.into_iter().map(State::from).collect());
+        }
+        Ok(Self::new(inputs, outputs, rows))
+    }
+
+    pub(crate) fn new(inputs: &[&str], outputs: &[&str], rows: Vec<Vec<State>>) -> Self {
+        Self {
+            inputs: inputs.iter().map(|name| String::from(*name)).collect(),
+            outputs: outputs.iter().map(|name| String::from(*name)).collect(),
+            rows,
+        }
+    }
+
+    pub fn get_inputs(&self) -> &[String] {
+        &self.inputs
+    }
+
+    pub fn get_outputs(&self) -> &[String] {
+        &self.outputs
+    }
+
+    /// Get the states of the outputs for the row of the given input states
+    pub fn get_row(&self, inputs: &[bool]) -> Option<&[State]> {
+        if inputs.len() != self.inputs.len() {
+            return None;
+        }
+        let index = inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, state)| **state)
+            .fold(0, |index, (bit, _)| index | 1 << bit);
+        self.rows.get(index).map(Vec::as_slice)
+    }
+
+    /// Get the rows of the table in order
+    pub fn get_rows(&self) -> &[Vec<State>] {
+        &self.rows
+    }
+
+    /// Compare the table with the expected one, outputs are matched by name
+    /// An output missing from one of the tables is reported as undefined in it.
+    pub fn compare(&self, expected: &TruthTable) -> Result<Vec<Mismatch>> {
+        if self.inputs != expected.inputs {
+            return Err(Error::InvalidLogic(format!(
+                "the tables have different inputs: {:?} and {:?}",
+                self.inputs, expected.inputs
+            )));
+        }
+        let mut outputs = self.outputs.clone();
+        outputs.extend(
+            expected
+                .outputs
+                .iter()
+                .filter(|output| !self.outputs.contains(output))
+                .cloned(),
+        );
+        let state = |table: &TruthTable, row: usize, output: &str| {
+            table
+                .outputs
+                .iter()
+                .position(|name| name == output)
+                .map_or(State::Undefined, |index| table.rows[row][index].clone())
+        };
+        let mut mismatches = vec![];
+        for row in 0..self.rows.len() {
+            for output in &outputs {
+                let actual = state(self, row, output);
+                let expected = state(expected, row, output);
+                if actual != expected {
+                    mismatches.push(Mismatch {
+                        inputs: self
+                            .inputs
+                            .iter()
+                            .cloned()
+                            .zip(bits(row, self.inputs.len()))
+                            .collect(),
+                        output: output.clone(),
+                        expected,
+                        actual,
+                    });
+                }
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// Compare the table with the outputs given by a function of the inputs
+    pub fn check<F>(&self, expected: F) -> Vec<Mismatch>
+    where
+        F: Fn(&[bool]) -> Vec<bool>,
+    {
+        let mut mismatches = vec![];
+        for (row, states) in self.rows.iter().enumerate() {
+            let inputs = bits(row, self.inputs.len());
+            let values = expected(&inputs);
+            for (index, (output, actual)) in self.outputs.iter().zip(states).enumerate() {
+                let expected = values
+                    .get(index)
+                    .map_or(State::Undefined, |value| State::from(*value));
+                if *actual != expected {
+                    mismatches.push(Mismatch {
+                        inputs: self.inputs.iter().cloned().zip(inputs.clone()).collect(),
+                        output: output.clone(),
+                        expected,
+                        actual: actual.clone(),
+                    });
+                }
+            }
+        }
+        mismatches
+    }
+}
+
+impl fmt::Display for TruthTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let widths: Vec<usize> = self
+            .inputs
+            .iter()
+            .chain(&self.outputs)
+            .map(|name| name.len().max(1))
+            .collect();
+        let names = self.inputs.iter().chain(&self.outputs);
+        for (index, (name, width)) in names.zip(&widths).enumerate() {
+            if index == self.inputs.len() {
+                write!(f, " |")?;
+            }
+            write!(f, " {name:>width$}")?;
+        }
+        for (row, states) in self.rows.iter().enumerate() {
+            writeln!(f)?;
+            let inputs = bits(row, self.inputs.len())
+                .into_iter()
+                .map(|bit| if bit { "1" } else { "0" });
+            let outputs = states.iter().map(|state| match state {
+                State::High => "1",
+                State::Low => "0",
+                State::Undefined => "X",
+            });
+            for (index, (value, width)) in inputs.chain(outputs).zip(&widths).enumerate() {
+                if index == self.inputs.len() {
+                    write!(f, " |")?;
+                }
+                write!(f, " {value:>width$}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn check_inputs(inputs: usize) -> Result<()> {
+    if inputs > TruthTable::MAX_INPUTS {
+        return Err(Error::InvalidLogic(format!(
+            "{inputs} inputs, the maximum is {}",
+            TruthTable::MAX_INPUTS
+        )));
+    }
+    Ok(())
+}
+
+/// Get the states of the inputs of a row
+fn bits(row: usize, inputs: usize) -> Vec<bool> {
+    (0..inputs).map(|bit| (row >> bit) & 1 == 1).collect()
+}
+
+pub(crate) fn extract(board: &mut Board, inputs: &[&str], outputs: &[&str]) -> Result<TruthTable> {
+    check_inputs(inputs.len())?;
+    let find = |name: &&str| {
+        board
+            .get_trace_by_name(name)
+            .ok_or_else(|| Error::UnknownTrace(String::from(*name)))
+    };
+    let input_traces = inputs.iter().map(find).collect::<Result<Vec<_>>>()?;
+    let output_traces = outputs.iter().map(find).collect::<Result<Vec<_>>>()?;
+    let forced: Vec<Option<State>> = input_traces
+        .iter()
+        .map(|trace| trace.borrow().get_forced())
+        .collect();
+
+    let mut rows = Vec::with_capacity(1 << inputs.len());
+    for row in 0..1usize << inputs.len() {
+        for (trace, state) in input_traces.iter().zip(bits(row, inputs.len())) {
+            trace.borrow_mut().force(State::from(state));
+        }
+        let settled = board
+            .settle(TruthTable::STEP, TruthTable::MAX_STEPS)
+            .is_some();
+        rows.push(
+            output_traces
+                .iter()
+                .map(|trace| {
+                    if settled {
+                        trace.borrow().get_state()
+                    } else {
+                        State::Undefined
+                    }
+                })
+                .collect(),
+        );
+    }
+    // put back the states the inputs were forced to before
+    for (trace, state) in input_traces.iter().zip(forced) {
+        match state {
+            Some(state) => trace.borrow_mut().force(state),
+            None => trace.borrow_mut().release(),
+        }
+    }
+    Ok(TruthTable::new(inputs, outputs, rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip::ChipRegistry;
+
+    #[test]
+    fn extraction_puts_back_the_forced_inputs() {
+        let mut board = Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part U1 virt_ic::GateAnd
+             net VCC PWR.1 U1.14
+             net GND PWR.2 U1.7
+             net A U1.1
+             net B U1.2
+             net Y U1.3",
+            &ChipRegistry::new(),
+        )
+        .unwrap();
+        let a = board.get_trace_by_name("A").unwrap();
+        let b = board.get_trace_by_name("B").unwrap();
+        a.borrow_mut().force(State::High);
+        let table = board.truth_table(&["A", "B"], &["Y"]).unwrap();
+        assert_eq!(table.get_row(&[true, true]), Some(&[State::High][..]));
+        assert_eq!(a.borrow().get_forced(), Some(State::High));
+        assert_eq!(b.borrow().get_forced(), None);
+    }
+}
@@ -0,0 +1,915 @@
+//! Structural Verilog export and import
+//!
+//! Each trace becomes a wire and each socket an instance of its chip type.
+//! Built-in gate chips are split into Verilog gate primitives (`and`, `or`, `not`, `nand`, `nor`)
+//! and generators into constant assignments, so the result can be simulated by an external HDL simulator.
+//! Other chips are instantiated as modules named after their chip type, with one port per pin (`p1`, `p2`, ...).
+//! ```text
+//! module board;
+//!     wire VCC;
+//!     wire GND;
+//!     wire N3;
+//!     // PWR: virt_ic::Generator
+//!     assign VCC = 1'b1;
+//!     assign GND = 1'b0;
+//!     // U1: virt_ic::GateAnd
+//!     and U1_1 (N3, VCC, VCC);
+//! endmodule
+//! ```
+//! The import reads back a subset of structural Verilog: a single module with scalar wires,
+//! `and`, `or`, `not`, `nand`, `nor` and `buf` primitives, constant assignments or aliases with `assign`,
+//! and instances of chip types known by the `ChipRegistry`, whose ports are either positional or named `p1`, `p2`, ...
+//! The gates are packed into the built-in gate chips, see `GateNetwork`.
+use super::{
+    chip::{
+        gates::{self, GateKind},
+        generators::Generator,
+        ChipRegistry,
+    },
+    naming,
+    network::GateNetwork,
+    Board, Chip, PinType,
+};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+const KEYWORDS: [&str; 26] = [
+    "always",
+    "and",
+    "assign",
+    "begin",
+    "buf",
+    "else",
+    "end",
+    "endmodule",
+    "if",
+    "initial",
+    "inout",
+    "input",
+    "module",
+    "nand",
+    "nor",
+    "not",
+    "or",
+    "output",
+    "reg",
+    "supply0",
+    "supply1",
+    "tri",
+    "wire",
+    "wor",
+    "xnor",
+    "xor",
+];
+
+/// Turn a name into a Verilog identifier, using an escaped identifier if needed
+pub(crate) fn identifier(name: &str) -> String {
+    let mut chars = name.chars();
+    let simple = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !KEYWORDS.contains(&name);
+    if simple {
+        String::from(name)
+    } else {
+        // escaped identifiers end with a whitespace
+        format!("\\{name} ")
+    }
+}
+
+/// Get the Verilog primitive of a gate
+pub(crate) fn primitive(kind: GateKind) -> &'static str {
+    match kind {
+        GateKind::And => "and",
+        GateKind::Or => "or",
+        GateKind::Not => "not",
+        GateKind::Nand => "nand",
+        GateKind::Nor => "nor",
+    }
+}
+
+/// Write a board as a structural Verilog module
+///
+/// Power pins of the gate chips are dropped since Verilog primitives don't have any,
+/// and unconnected gate inputs are tied to the state the gates read a floating pin as:
+/// `1'b1` for the `Gate3InputNor`, `1'b0` for the other gates.
+/// Traces driven by several outputs are declared as `wor`, to match how a trace resolves its state.
+/// If a pin is linked by several traces, only the first one is used.
+/// Nets and instances share a single namespace: a name already taken gets a `_2`, `_3`, ... suffix.
+pub fn write(board: &Board, module_name: &str) -> String {
+    let mut output = format!("module {};\n", identifier(module_name));
+
+    let mut taken = HashSet::new();
+    let mut nets: HashMap<(u128, u8), String> = HashMap::new();
+    for (trace, name) in board.get_traces().iter().zip(naming::trace_names(board)) {
+        let name = identifier(&naming::unique_name(&mut taken, name));
+        let pins = trace.borrow().get_pins();
+        let drivers = pins
+            .iter()
+            .filter(|pin| pin.borrow().pin_type == PinType::Output)
+            .count();
+        let net_type = if drivers > 1 { "wor" } else { "wire" };
+        output.push_str(&format!("    {net_type} {name};\n"));
+        for pin in pins {
+            let pin = pin.borrow();
+            nets.entry((pin.parent, pin.number))
+                .or_insert_with(|| name.clone());
+        }
+    }
+
+    for (socket, name) in board.get_sockets().iter().zip(naming::socket_names(board)) {
+        let socket = socket.borrow();
+        if !socket.has_chip() {
+            continue;
+        }
+        let uuid = socket.get_uuid();
+        let chip_type = socket.get_type();
+        output.push_str(&format!("    // {name}: {chip_type}\n"));
+        if let Some(gates) = gates::get_gates(chip_type) {
+            let floating = if gates::get_quirks(chip_type).undefined_as_high {
+                "1'b1"
+            } else {
+                "1'b0"
+            };
+            for (index, gate) in gates.iter().enumerate() {
+                if let Some(out) = nets.get(&(uuid, gate.output)) {
+                    let mut ports = vec![out.clone()];
+                    for input in gate.inputs {
+                        ports.push(
+                            nets.get(&(uuid, *input))
+                                .cloned()
+                                .unwrap_or_else(|| String::from(floating)),
+                        );
+                    }
+                    output.push_str(&format!(
+                        "    {} {} ({});\n",
+                        primitive(gate.kind),
+                        identifier(&naming::unique_name(
+                            &mut taken,
+                            format!("{name}_{}", index + 1)
+                        )),
+                        ports.join(", ")
+                    ));
+                }
+            }
+        } else if chip_type == Generator::TYPE {
+            if let Some(vcc) = nets.get(&(uuid, Generator::VCC)) {
+                output.push_str(&format!("    assign {vcc} = 1'b1;\n"));
+            }
+            if let Some(gnd) = nets.get(&(uuid, Generator::GND)) {
+                output.push_str(&format!("    assign {gnd} = 1'b0;\n"));
+            }
+        } else {
+            let ports: Vec<String> = (1..=socket.get_pin_qty())
+                .map(|number| {
+                    format!(
+                        ".p{number}({})",
+                        nets.get(&(uuid, number)).map_or("", String::as_str)
+                    )
+                })
+                .collect();
+            output.push_str(&format!(
+                "    {} {} ({});\n",
+                identifier(chip_type),
+                identifier(&naming::unique_name(&mut taken, name)),
+                ports.join(", ")
+            ));
+        }
+    }
+    output.push_str("endmodule\n");
+    output
+}
+
+/// An error encountered while parsing Verilog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerilogError {
+    /// Line of the source where the error occured, starting at 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl VerilogError {
+    fn new(line: usize, message: String) -> Self {
+        Self { line, message }
+    }
+}
+
+impl fmt::Display for VerilogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for VerilogError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Number(String),
+    Symbol(char),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ident(ident) | Self::Number(ident) => write!(f, "`{ident}`"),
+            Self::Symbol(symbol) => write!(f, "`{symbol}`"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, VerilogError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            let start_line = line;
+            i += 2;
+            loop {
+                match chars.get(i) {
+                    None => {
+                        return Err(VerilogError::new(
+                            start_line,
+                            String::from("unterminated comment"),
+                        ))
+                    }
+                    Some('*') if chars.get(i + 1) == Some(&'/') => {
+                        i += 2;
+                        break;
+                    }
+                    Some('\n') => line += 1,
+                    Some(_) => {}
+                }
+                i += 1;
+            }
+        } else if c == '\\' {
+            // escaped identifier, until the next whitespace
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && !chars[i].is_whitespace() {
+                i += 1;
+            }
+            tokens.push((line, Token::Ident(chars[start..i].iter().collect())));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '$')
+            {
+                i += 1;
+            }
+            tokens.push((line, Token::Ident(chars[start..i].iter().collect())));
+        } else if c.is_ascii_digit() || c == '\'' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '\'')
+            {
+                i += 1;
+            }
+            tokens.push((line, Token::Number(chars[start..i].iter().collect())));
+        } else if "(),;.=#[]:".contains(c) {
+            tokens.push((line, Token::Symbol(c)));
+            i += 1;
+        } else {
+            return Err(VerilogError::new(
+                line,
+                format!("unexpected character `{c}`"),
+            ));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Read a single bit constant like `1'b0` or `1'b1`
+///
+/// Returns `Some(None)` for `x` and `z` constants, that leave the pin floating
+fn constant(number: &str) -> Option<Option<&'static str>> {
+    let digits = match number.split_once('\'') {
+        Some((_, value)) => value.trim_start_matches(['s', 'S']).get(1..).unwrap_or(""),
+        None => number,
+    };
+    let digits = digits.replace('_', "").to_ascii_lowercase();
+    if !digits.is_empty() && digits.chars().all(|c| c == '0') {
+        Some(Some(GateNetwork::LOW))
+    } else if digits.trim_start_matches('0') == "1" {
+        Some(Some(GateNetwork::HIGH))
+    } else if digits == "x" || digits == "z" || digits == "?" {
+        Some(None)
+    } else {
+        None
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or_else(|| self.tokens.last())
+            .map_or(1, |(line, _)| *line)
+    }
+
+    fn error(&self, message: String) -> VerilogError {
+        VerilogError::new(self.line(), message)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Result<Token, VerilogError> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| self.error(String::from("unexpected end of file")))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), VerilogError> {
+        match self.next()? {
+            Token::Symbol(c) if c == symbol => Ok(()),
+            token => {
+                self.position -= 1;
+                Err(self.error(format!("expected `{symbol}`, found {token}")))
+            }
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, VerilogError> {
+        match self.next()? {
+            Token::Ident(ident) => Ok(ident),
+            token => {
+                self.position -= 1;
+                Err(self.error(format!("expected an identifier, found {token}")))
+            }
+        }
+    }
+
+    /// Read a net or a constant, `None` is a floating constant
+    fn terminal(&mut self) -> Result<Option<String>, VerilogError> {
+        match self.next()? {
+            Token::Ident(ident) => Ok(Some(ident)),
+            Token::Number(number) => match constant(&number) {
+                Some(value) => Ok(value.map(String::from)),
+                None => {
+                    self.position -= 1;
+                    Err(self.error(format!(
+                        "unsupported constant `{number}`, only single bits are supported"
+                    )))
+                }
+            },
+            Token::Symbol('[') => {
+                self.position -= 1;
+                Err(self.error(String::from("vectors are not supported")))
+            }
+            token => {
+                self.position -= 1;
+                Err(self.error(format!("expected a net, found {token}")))
+            }
+        }
+    }
+
+    /// Read a comma separated list of identifiers ended by a `;`
+    fn declaration(&mut self) -> Result<Vec<String>, VerilogError> {
+        if self.peek() == Some(&Token::Symbol('[')) {
+            return Err(self.error(String::from("vectors are not supported")));
+        }
+        let mut names = vec![];
+        loop {
+            let name = self.ident()?;
+            if name != "wire" {
+                names.push(name);
+                if !self.eat(',') {
+                    break;
+                }
+            }
+        }
+        self.expect(';')?;
+        Ok(names)
+    }
+}
+
+struct ParsedGate {
+    line: usize,
+    kind: GateKind,
+    inputs: Vec<Option<String>>,
+    output: Option<String>,
+}
+
+struct ParsedInstance {
+    line: usize,
+    chip_type: String,
+    name: String,
+    connections: Vec<(u8, Option<String>)>,
+}
+
+/// Nets linked together by `assign` statements
+#[derive(Default)]
+struct Aliases {
+    parents: HashMap<String, String>,
+}
+
+impl Aliases {
+    fn resolve(&self, net: &str) -> String {
+        let mut net = net;
+        while let Some(parent) = self.parents.get(net) {
+            net = parent;
+        }
+        String::from(net)
+    }
+
+    fn link(&mut self, a: &str, b: &str, ports: &HashSet<String>) -> Result<(), String> {
+        let a = self.resolve(a);
+        let b = self.resolve(b);
+        if a == b {
+            return Ok(());
+        }
+        let is_constant = |net: &str| net == GateNetwork::HIGH || net == GateNetwork::LOW;
+        if is_constant(&a) && is_constant(&b) {
+            return Err(String::from("assigning a constant to another constant"));
+        }
+        // keep constants first, then the ports of the module
+        let keep_a = is_constant(&a) || (!is_constant(&b) && ports.contains(&a));
+        if keep_a {
+            self.parents.insert(b, a);
+        } else {
+            self.parents.insert(a, b);
+        }
+        Ok(())
+    }
+}
+
+fn gate_kind(primitive: &str) -> Option<Option<GateKind>> {
+    match primitive {
+        "and" => Some(Some(GateKind::And)),
+        "or" => Some(Some(GateKind::Or)),
+        "not" => Some(Some(GateKind::Not)),
+        "nand" => Some(Some(GateKind::Nand)),
+        "nor" => Some(Some(GateKind::Nor)),
+        // a buffer is an AND gate with a single input
+        "buf" => Some(None),
+        _ => None,
+    }
+}
+
+/// Parse a structural Verilog module and build the corresponding board
+///
+/// Chips are created with the given `ChipRegistry`, `ChipRegistry::new()` knows the built-in chips
+pub fn parse(input: &str, registry: &ChipRegistry) -> Result<Board, VerilogError> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        position: 0,
+    };
+    let mut ports: HashSet<String> = HashSet::new();
+    let mut assigns: Vec<(usize, String, Option<String>)> = vec![];
+    let mut parsed_gates: Vec<ParsedGate> = vec![];
+    let mut instances: Vec<ParsedInstance> = vec![];
+
+    match parser.next()? {
+        Token::Ident(keyword) if keyword == "module" => {}
+        token => {
+            parser.position -= 1;
+            return Err(parser.error(format!("expected `module`, found {token}")));
+        }
+    }
+    parser.ident()?;
+    if parser.eat('(') && !parser.eat(')') {
+        loop {
+            let mut name = parser.ident()?;
+            while ["input", "output", "inout", "wire"].contains(&name.as_str()) {
+                if parser.peek() == Some(&Token::Symbol('[')) {
+                    return Err(parser.error(String::from("vectors are not supported")));
+                }
+                name = parser.ident()?;
+            }
+            ports.insert(name);
+            if !parser.eat(',') {
+                break;
+            }
+        }
+        parser.expect(')')?;
+    }
+    parser.expect(';')?;
+
+    loop {
+        let line = parser.line();
+        let keyword = match parser.next()? {
+            Token::Ident(keyword) => keyword,
+            token => {
+                parser.position -= 1;
+                return Err(parser.error(format!("expected a statement, found {token}")));
+            }
+        };
+        match keyword.as_str() {
+            "endmodule" => break,
+            "input" | "output" | "inout" => ports.extend(parser.declaration()?),
+            "wire" | "wor" | "tri" => {
+                parser.declaration()?;
+            }
+            "assign" => {
+                loop {
+                    let line = parser.line();
+                    let target = parser.ident()?;
+                    parser.expect('=')?;
+                    let value = parser.terminal()?;
+                    assigns.push((line, target, value));
+                    if !parser.eat(',') {
+                        break;
+                    }
+                }
+                parser.expect(';')?;
+            }
+            "module" => return Err(parser.error(String::from("nested modules are not supported"))),
+            primitive if gate_kind(primitive).is_some() => {
+                let kind = gate_kind(primitive).flatten();
+                loop {
+                    if parser.peek() == Some(&Token::Symbol('#')) {
+                        return Err(parser.error(String::from("delays are not supported")));
+                    }
+                    if let Some(Token::Ident(_)) = parser.peek() {
+                        parser.ident()?;
+                    }
+                    parser.expect('(')?;
+                    let mut terminals = vec![parser.terminal()?];
+                    while parser.eat(',') {
+                        terminals.push(parser.terminal()?);
+                    }
+                    parser.expect(')')?;
+                    if terminals.len() < 2 {
+                        return Err(VerilogError::new(
+                            line,
+                            format!("`{primitive}` needs an output and at least one input"),
+                        ));
+                    }
+                    match kind {
+                        Some(kind) if kind != GateKind::Not => parsed_gates.push(ParsedGate {
+                            line,
+                            kind,
+                            output: terminals.remove(0),
+                            inputs: terminals,
+                        }),
+                        _ => {
+                            // `not` and `buf` can drive several outputs from their last terminal
+                            let input = terminals.pop().unwrap_or(None);
+                            for output in terminals {
+                                parsed_gates.push(ParsedGate {
+                                    line,
+                                    kind: kind.unwrap_or(GateKind::And),
+                                    inputs: vec![input.clone()],
+                                    output,
+                                });
+                            }
+                        }
+                    }
+                    if !parser.eat(',') {
+                        break;
+                    }
+                }
+                parser.expect(';')?;
+            }
+            "xor" | "xnor" | "bufif0" | "bufif1" | "notif0" | "notif1" | "reg" | "always"
+            | "initial" | "supply0" | "supply1" => {
+                return Err(VerilogError::new(
+                    line,
+                    format!("`{keyword}` is not supported"),
+                ));
+            }
+            chip_type => {
+                if parser.peek() == Some(&Token::Symbol('#')) {
+                    return Err(parser.error(String::from("parameters are not supported")));
+                }
+                let name = parser.ident()?;
+                parser.expect('(')?;
+                let mut connections = vec![];
+                if !parser.eat(')') {
+                    let mut position = 1;
+                    loop {
+                        if parser.eat('.') {
+                            let port = parser.ident()?;
+                            let number = port
+                                .strip_prefix('p')
+                                .and_then(|number| number.parse::<u8>().ok())
+                                .ok_or_else(|| {
+                                    parser.error(format!(
+                                        "invalid port `{port}`, expected `p1`, `p2`, ..."
+                                    ))
+                                })?;
+                            parser.expect('(')?;
+                            let net = if parser.peek() == Some(&Token::Symbol(')')) {
+                                None
+                            } else {
+                                parser.terminal()?
+                            };
+                            parser.expect(')')?;
+                            connections.push((number, net));
+                        } else if parser.peek() == Some(&Token::Symbol(','))
+                            || parser.peek() == Some(&Token::Symbol(')'))
+                        {
+                            // empty positional connection
+                            connections.push((position, None));
+                        } else {
+                            connections.push((position, parser.terminal()?));
+                        }
+                        position = position.saturating_add(1);
+                        if !parser.eat(',') {
+                            break;
+                        }
+                    }
+                    parser.expect(')')?;
+                }
+                parser.expect(';')?;
+                instances.push(ParsedInstance {
+                    line,
+                    chip_type: String::from(chip_type),
+                    name,
+                    connections,
+                });
+            }
+        }
+    }
+    if parser.peek().is_some() {
+        return Err(parser.error(String::from("only a single module is supported")));
+    }
+
+    let mut aliases = Aliases::default();
+    for (line, target, value) in &assigns {
+        if let Some(value) = value {
+            aliases
+                .link(target, value, &ports)
+                .map_err(|message| VerilogError::new(*line, message))?;
+        }
+    }
+
+    let mut network = GateNetwork::new();
+    for gate in &parsed_gates {
+        let output = gate.output.as_ref().map(|net| aliases.resolve(net));
+        let output = match output {
+            Some(net) if net != GateNetwork::HIGH && net != GateNetwork::LOW => net,
+            _ => {
+                return Err(VerilogError::new(
+                    gate.line,
+                    String::from("the output of a gate must be a net"),
+                ))
+            }
+        };
+        // floating inputs are tied Low, whatever the gate chip they're packed into reads them as
+        let inputs: Vec<String> = gate
+            .inputs
+            .iter()
+            .map(|net| {
+                net.as_ref().map_or_else(
+                    || String::from(GateNetwork::LOW),
+                    |net| aliases.resolve(net),
+                )
+            })
+            .collect();
+        let inputs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+        network
+            .add_gate(gate.kind, &inputs, &output)
+            .map_err(|err| VerilogError::new(gate.line, err.to_string()))?;
+    }
+    let mut names = HashSet::new();
+    for instance in instances {
+        let chip = registry.create(&instance.chip_type).ok_or_else(|| {
+            VerilogError::new(
+                instance.line,
+                format!("unknown chip type `{}`", instance.chip_type),
+            )
+        })?;
+        if !names.insert(instance.name.clone()) {
+            return Err(VerilogError::new(
+                instance.line,
+                format!("instance `{}` is already declared", instance.name),
+            ));
+        }
+        let mut connections = vec![];
+        for (number, net) in instance.connections {
+            if number == 0 || number > chip.get_pin_qty() {
+                return Err(VerilogError::new(
+                    instance.line,
+                    format!(
+                        "`{}` has no pin {number}, it only has {} pins",
+                        instance.chip_type,
+                        chip.get_pin_qty()
+                    ),
+                ));
+            }
+            if let Some(net) = net {
+                connections.push((number, aliases.resolve(&net)));
+            }
+        }
+        network.add_chip(&instance.name, chip, connections);
+    }
+    Ok(network.build_board())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::State;
+
+    #[test]
+    fn exported_boards_import_to_the_same_logic() {
+        let mut board = Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part U1 virt_ic::Gate3InputNor
+             part U2 virt_ic::GateNand
+             net VCC PWR.1 U1.14 U2.14
+             net GND PWR.2 U1.7 U2.7
+             net A U1.1 U2.1
+             net B U1.2 U2.2
+             net X U1.12 U2.4
+             net C U2.5
+             net Y U2.6",
+            &ChipRegistry::new(),
+        )
+        .unwrap();
+        // the third input of the NOR is left floating
+        let source = write(&board, "top");
+        assert!(source.contains("nor U1_1 (X, A, B, 1'b1);"));
+        let mut imported = parse(&source, &ChipRegistry::new()).unwrap();
+        let inputs = ["A", "B", "C"];
+        let outputs = ["X", "Y"];
+        assert_eq!(
+            imported.truth_table(&inputs, &outputs).unwrap(),
+            board.truth_table(&inputs, &outputs).unwrap()
+        );
+    }
+
+    fn error(input: &str) -> VerilogError {
+        match parse(input, &ChipRegistry::new()) {
+            Ok(_) => panic!("{:?} parsed", input),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn malformed_sources_are_errors() {
+        let line = |body: &str| error(&format!("module top(A, Y);\n{body}\nendmodule")).line;
+        assert_eq!(error("").line, 1);
+        assert_eq!(error("wire A;").line, 1);
+        assert_eq!(error("module top(A, Y)\nendmodule").line, 2);
+        assert_eq!(error("module top;\nwire A;").line, 2);
+        assert_eq!(
+            error("module top;\nendmodule\nmodule other;\nendmodule").line,
+            3
+        );
+        assert_eq!(error("module top;\n/* comment").line, 2);
+        assert_eq!(line("and (Y, A)"), 3);
+        assert_eq!(line("and (Y, A;"), 2);
+        assert_eq!(line("and #1 (Y, A, A);"), 2);
+        assert_eq!(line("and (Y);"), 2);
+        assert_eq!(line("and (1'b1, A, A);"), 2);
+        assert_eq!(line("and (Y, A, 2'b10);"), 2);
+        assert_eq!(line("xor (Y, A, A);"), 2);
+        assert_eq!(line("wire [3:0] B;"), 2);
+        assert_eq!(line("assign 1'b1 = A;"), 2);
+        assert_eq!(line("assign Y = A @ A;"), 2);
+        assert_eq!(line("\\virt_ic::Unknown U1 (A, Y);"), 2);
+        assert_eq!(line("\\virt_ic::GateNot U1 (.q(A));"), 2);
+        assert_eq!(line("\\virt_ic::GateNot U1 (.p15(A));"), 2);
+        assert_eq!(
+            line("\\virt_ic::GateNot U1 ();\n\\virt_ic::GateNot U1 ();"),
+            3
+        );
+        assert_eq!(line("module inner;"), 2);
+    }
+
+    #[test]
+    fn gates_and_assignments_import() {
+        let mut board = parse(
+            "// a NAND from an AND and a NOT
+             module top(input A, input B, output Y);
+                 wire N;
+                 assign C = A;
+                 and g1 (N, C, B);
+                 not g2 (Y, N);
+             endmodule",
+            &ChipRegistry::new(),
+        )
+        .unwrap();
+        let table = board.truth_table(&["A", "B"], &["Y"]).unwrap();
+        let mut expected = Board::from_netlist(
+            "part PWR virt_ic::Generator
+             part U1 virt_ic::GateNand
+             net VCC PWR.1 U1.14
+             net GND PWR.2 U1.7
+             net A U1.1
+             net B U1.2
+             net Y U1.3",
+            &ChipRegistry::new(),
+        )
+        .unwrap();
+        assert_eq!(table, expected.truth_table(&["A", "B"], &["Y"]).unwrap());
+    }
+
+    #[test]
+    fn colliding_names_are_made_unique() {
+        let mut board = Board::new();
+        let power = board.new_socket_with(Box::new(Generator::new()));
+        let gate = board.new_socket_with(Box::new(gates::GateNot::new()));
+        gate.borrow_mut().set_name("U1");
+        let clock = board.new_socket_with(Box::new(crate::chip::clocks::Clock1kHz::new()));
+        clock.borrow_mut().set_name("IN");
+        let nets = [
+            ("VCC", &power, Generator::VCC),
+            ("VCC", &gate, 14),
+            ("GND", &power, Generator::GND),
+            ("GND", &gate, 7),
+            ("IN", &gate, 1),
+            ("OUT", &gate, 2),
+            ("IN", &gate, 3),
+            ("U1_1", &gate, 4),
+        ];
+        let mut traces: HashMap<&str, _> = HashMap::new();
+        for (index, (name, socket, pin)) in nets.iter().enumerate() {
+            // the power nets are single traces, the other names are given to two traces
+            let trace = if index < 4 {
+                traces
+                    .entry(*name)
+                    .or_insert_with(|| {
+                        let trace = board.new_trace();
+                        trace.borrow_mut().set_name(name);
+                        trace
+                    })
+                    .clone()
+            } else {
+                let trace = board.new_trace();
+                trace.borrow_mut().set_name(name);
+                trace
+            };
+            trace
+                .borrow_mut()
+                .connect(socket.borrow_mut().get_pin(*pin).unwrap());
+        }
+
+        let source = write(&board, "top");
+        let mut declared = HashSet::new();
+        for line in source.lines().map(str::trim) {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("wire" | "wor" | "not") | Some("\\virt_ic::Clock1kHz") => {
+                    let name = tokens.next().unwrap().trim_end_matches(';');
+                    assert!(
+                        declared.insert(String::from(name)),
+                        "{} in {}",
+                        name,
+                        source
+                    );
+                }
+                _ => {}
+            }
+        }
+        assert!(declared.contains("IN_2"));
+        assert!(declared.contains("IN_3"));
+        assert!(declared.contains("U1_1_2"));
+
+        let mut imported = parse(&source, &ChipRegistry::new()).unwrap();
+        let force = |name: &str, state: State| {
+            let trace = imported.get_trace_by_name(name).unwrap();
+            trace.borrow_mut().force(state);
+        };
+        force("IN", State::High);
+        force("IN_2", State::Low);
+        imported.run_during(
+            std::time::Duration::from_millis(3),
+            std::time::Duration::from_millis(1),
+        );
+        let state = |name: &str| {
+            imported
+                .get_trace_by_name(name)
+                .unwrap()
+                .borrow()
+                .get_state()
+        };
+        assert_eq!(state("OUT"), State::Low);
+        assert_eq!(state("U1_1"), State::High);
+    }
+}